@@ -4,13 +4,23 @@
 
 //! Driver for the Arm Generic Interrupt Controller version 2.
 
+pub mod hypervisor;
+pub mod msi;
 pub mod registers;
 
 pub use self::registers::Typer;
-use self::registers::{Gicc, Gicd, GicdCtlr};
-use crate::{IntId, Trigger};
+use self::registers::{Gicc, GiccCtlr, Gicd, GicdCtlr};
+use crate::{IntId, Trigger, preemption_levels};
 use core::ptr::NonNull;
-use safe_mmio::{UniqueMmioPointer, field, field_shared};
+use safe_mmio::{SharedMmioPointer, UniqueMmioPointer, field, field_shared, fields::ReadPureWrite};
+use thiserror::Error;
+
+/// An error which may be returned from operations on a GIC Distributor.
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GICDError {
+    #[error("{0:?} is not an SPI")]
+    NotAnSpi(IntId),
+}
 
 /// Driver for an Arm Generic Interrupt Controller version 2.
 #[derive(Debug)]
@@ -52,11 +62,68 @@ impl GicV2<'_> {
             field!(self.gicd, igroupr).get(i).unwrap().write(0xffffffff);
         }
 
-        field!(self.gicc, ctlr).write(0b1);
+        // In the non-secure copy of GICC_CTLR this bit enables group 1 instead.
+        field!(self.gicc, ctlr).write(GiccCtlr::EnableGrp0);
         field!(self.gicc, pmr).write(0xff);
     }
 
+    /// Enables or disables forwarding of group 0 interrupts from the distributor to the CPU
+    /// interfaces.
+    ///
+    /// This is only possible from the secure state, or in a GIC without the security extensions.
+    pub fn enable_group0(&mut self, enable: bool) {
+        self.modify_gicd_ctlr(GicdCtlr::EnableGrp0, enable);
+    }
+
+    /// Enables or disables forwarding of group 1 interrupts from the distributor to the CPU
+    /// interfaces.
+    pub fn enable_group1(&mut self, enable: bool) {
+        self.modify_gicd_ctlr(GicdCtlr::EnableGrp1, enable);
+    }
+
+    fn modify_gicd_ctlr(&mut self, flags: GicdCtlr, set: bool) {
+        let mut ctlr = field!(self.gicd, ctlr);
+        let old = ctlr.read();
+        ctlr.write(if set { old | flags } else { old - flags });
+    }
+
+    /// Returns the value of the CPU interface control register for the current CPU.
+    pub fn cpu_interface_control(&self) -> GiccCtlr {
+        field_shared!(self.gicc, ctlr).read()
+    }
+
+    /// Sets the CPU interface control register for the current CPU.
+    ///
+    /// For example, secure firmware can set `GiccCtlr::FIQEn` to signal group 0 interrupts as FIQs.
+    pub fn set_cpu_interface_control(&mut self, ctlr: GiccCtlr) {
+        field!(self.gicc, ctlr).write(ctlr);
+    }
+
+    /// Assigns the interrupt with the given ID to the given group.
+    ///
+    /// This is only possible from the secure state, or in a GIC without the security extensions.
+    pub fn set_group(&mut self, intid: IntId, group: Group) {
+        let mut igroupr = field!(self.gicd, igroupr);
+        let mut register = igroupr.get((intid.0 / 32) as usize).unwrap();
+        let bit = 1 << (intid.0 % 32);
+        let v = register.read();
+        register.write(match group {
+            Group::Group0 => v & !bit,
+            Group::Group1 => v | bit,
+        });
+    }
+
+    /// Returns the group of the given interrupt.
+    pub fn group(&self, intid: IntId) -> Group {
+        if Self::read_bit(field_shared!(self.gicd, igroupr).into(), intid) {
+            Group::Group1
+        } else {
+            Group::Group0
+        }
+    }
+
     /// Enables or disables the interrupt with the given ID.
+    #[allow(clippy::result_unit_err)]
     pub fn enable_interrupt(&mut self, intid: IntId, enable: bool) -> Result<(), ()> {
         let index = (intid.0 / 32) as usize;
         let bit = 1 << (intid.0 % 32);
@@ -131,6 +198,154 @@ impl GicV2<'_> {
         });
     }
 
+    /// Returns whether the given interrupt is enabled.
+    pub fn is_enabled(&self, intid: IntId) -> bool {
+        Self::read_bit(field_shared!(self.gicd, isenabler).into(), intid)
+    }
+
+    /// Returns the priority of the given interrupt.
+    ///
+    /// Bits of the priority which are not implemented read as zero.
+    pub fn priority(&self, intid: IntId) -> u8 {
+        let idx = intid.0 as usize / 4;
+        let priorities = field_shared!(self.gicd, ipriorityr)
+            .get(idx)
+            .unwrap()
+            .read();
+        (priorities >> (8 * (intid.0 % 4))) as u8
+    }
+
+    /// Returns the trigger type of the given interrupt.
+    pub fn trigger(&self, intid: IntId) -> Trigger {
+        let index = (intid.0 / 16) as usize;
+        let bit = 1 << (((intid.0 % 16) * 2) + 1);
+
+        if field_shared!(self.gicd, icfgr).get(index).unwrap().read() & bit != 0 {
+            Trigger::Edge
+        } else {
+            Trigger::Level
+        }
+    }
+
+    /// Returns a snapshot of the configuration of the given interrupt.
+    ///
+    /// Unlike on GICv3, there is no way to choose the CPU core for an SGI or PPI: the banked
+    /// registers of the current CPU interface are always read.
+    pub fn interrupt_config(&self, intid: IntId) -> InterruptConfig {
+        InterruptConfig {
+            enabled: self.is_enabled(intid),
+            priority: self.priority(intid),
+            trigger: self.trigger(intid),
+            group: self.group(intid),
+            targets: self.targets(intid),
+        }
+    }
+
+    /// Sets the CPU interfaces to which the given SPI is forwarded.
+    ///
+    /// Each bit of `cpu_mask` corresponds to a CPU interface; i.e. bit 0 is CPU interface 0.
+    ///
+    /// Returns an error if the interrupt is not an SPI. The targets of SGIs and PPIs are fixed.
+    pub fn set_targets(&mut self, intid: IntId, cpu_mask: u8) -> Result<(), GICDError> {
+        if !intid.is_spi() {
+            return Err(GICDError::NotAnSpi(intid));
+        }
+
+        field!(self.gicd, itargetsr)
+            .get(intid.0 as usize)
+            .unwrap()
+            .write(cpu_mask);
+        Ok(())
+    }
+
+    /// Returns the mask of CPU interfaces to which the given interrupt is forwarded.
+    ///
+    /// For SGIs and PPIs this is the current CPU interface.
+    pub fn targets(&self, intid: IntId) -> u8 {
+        field_shared!(self.gicd, itargetsr)
+            .get(intid.0 as usize)
+            .unwrap()
+            .read()
+    }
+
+    /// Returns the number of the current CPU's interface, read from the banked targets registers
+    /// for SGIs and PPIs.
+    ///
+    /// This need not be the same as any field of the CPU's MPIDR. Returns `None` if the targets
+    /// registers read as zero, as they may in a uniprocessor implementation.
+    pub fn current_cpu_interface(&self) -> Option<u8> {
+        let itargetsr = field_shared!(self.gicd, itargetsr);
+        (0..IntId::SPI_START as usize)
+            .map(|i| itargetsr.get(i).unwrap().read())
+            .find(|&mask| mask != 0)
+            .map(|mask| mask.trailing_zeros() as u8)
+    }
+
+    /// Sets the given interrupt to be pending.
+    ///
+    /// SGIs and PPIs are set pending on the current CPU interface. An SGI is set pending as if it
+    /// were sent by the current CPU interface.
+    pub fn set_pending(&mut self, intid: IntId) {
+        if intid.is_sgi() {
+            let source = 1 << self.current_cpu_interface().unwrap_or_default();
+            field!(self.gicd, spendsgir)
+                .get(intid.0 as usize)
+                .unwrap()
+                .write(source);
+        } else {
+            Self::write_bit(field!(self.gicd, ispendr).into(), intid);
+        }
+    }
+
+    /// Clears the pending state of the given interrupt.
+    ///
+    /// For an SGI this clears it for all source CPU interfaces.
+    pub fn clear_pending(&mut self, intid: IntId) {
+        if intid.is_sgi() {
+            field!(self.gicd, cpendsgir)
+                .get(intid.0 as usize)
+                .unwrap()
+                .write(0xff);
+        } else {
+            Self::write_bit(field!(self.gicd, icpendr).into(), intid);
+        }
+    }
+
+    /// Returns whether the given interrupt is pending.
+    pub fn is_pending(&self, intid: IntId) -> bool {
+        Self::read_bit(field_shared!(self.gicd, ispendr).into(), intid)
+    }
+
+    /// Sets the given interrupt to be active.
+    pub fn set_active(&mut self, intid: IntId) {
+        Self::write_bit(field!(self.gicd, isactiver).into(), intid);
+    }
+
+    /// Clears the active state of the given interrupt.
+    ///
+    /// This may be used to recover an interrupt which was acknowledged but never ended.
+    pub fn clear_active(&mut self, intid: IntId) {
+        Self::write_bit(field!(self.gicd, icactiver).into(), intid);
+    }
+
+    /// Returns whether the given interrupt is active.
+    pub fn is_active(&self, intid: IntId) -> bool {
+        Self::read_bit(field_shared!(self.gicd, isactiver).into(), intid)
+    }
+
+    /// Writes only the bit for the given interrupt to the given set or clear registers.
+    fn write_bit(mut registers: UniqueMmioPointer<[ReadPureWrite<u32>]>, intid: IntId) {
+        registers
+            .get((intid.0 / 32) as usize)
+            .unwrap()
+            .write(1 << (intid.0 % 32));
+    }
+
+    /// Reads the bit for the given interrupt from the given registers.
+    fn read_bit(registers: SharedMmioPointer<[ReadPureWrite<u32>]>, intid: IntId) -> bool {
+        registers.get((intid.0 / 32) as usize).unwrap().read() & (1 << (intid.0 % 32)) != 0
+    }
+
     /// Sends a software-generated interrupt (SGI) to the given cores.
     pub fn send_sgi(&mut self, intid: IntId, target: SgiTarget) {
         assert!(intid.is_sgi());
@@ -169,9 +384,171 @@ impl GicV2<'_> {
 
     /// Informs the interrupt controller that the CPU has completed processing the given interrupt.
     /// This drops the interrupt priority and deactivates the interrupt.
+    ///
+    /// If split EOI has been enabled with [`GicV2::enable_split_eoi`] then this only drops the
+    /// priority, and the interrupt must later be deactivated with
+    /// [`GicV2::deactivate_interrupt`].
     pub fn end_interrupt(&mut self, intid: IntId) {
         field!(self.gicc, aeoir).write(intid.0);
     }
+
+    /// Deactivates the given interrupt, after its priority has been dropped by
+    /// [`GicV2::end_interrupt`].
+    ///
+    /// This should only be used if split EOI has been enabled with [`GicV2::enable_split_eoi`].
+    pub fn deactivate_interrupt(&mut self, intid: IntId) {
+        field!(self.gicc, dir).write(intid.0);
+    }
+
+    /// Enables or disables split EOI for the current CPU interface, by setting the EOImode bits
+    /// of `GICC_CTLR`.
+    ///
+    /// `view` must say which copy of `GICC_CTLR` the GIC is accessed through. In the secure copy,
+    /// or in a GIC without the security extensions, this sets both `EOImodeS` and `EOImodeNS` so
+    /// that split EOI applies to both groups. In the non-secure copy it sets bit 9, which there
+    /// is `EOImodeNS`.
+    ///
+    /// When enabled, [`GicV2::end_interrupt`] only drops the priority of the interrupt, and
+    /// [`GicV2::deactivate_interrupt`] must be called separately to deactivate it. This should be
+    /// called after [`GicV2::setup`], which disables it.
+    pub fn enable_split_eoi(&mut self, enable: bool, view: GiccCtlrView) {
+        let flags = match view {
+            GiccCtlrView::Secure => GiccCtlr::EOImodeS | GiccCtlr::EOImodeNS,
+            // Bit 9 is EOImodeNS in the non-secure copy, and bit 10 is reserved.
+            GiccCtlrView::NonSecure => GiccCtlr::EOImodeS,
+        };
+        let mut ctlr = field!(self.gicc, ctlr);
+        let old = ctlr.read();
+        ctlr.write(if enable { old | flags } else { old - flags });
+    }
+
+    /// Returns the binary point for the given interrupt group on the current CPU interface, from
+    /// `GICC_BPR` for group 0 or `GICC_ABPR` for group 1.
+    pub fn binary_point(&self, group: Group) -> u8 {
+        let bpr = match group {
+            Group::Group0 => field_shared!(self.gicc, bpr).read(),
+            Group::Group1 => field_shared!(self.gicc, abpr).read(),
+        };
+        (bpr & 0b111) as u8
+    }
+
+    /// Sets the binary point for the given interrupt group on the current CPU interface.
+    ///
+    /// This splits interrupt priorities into a group priority field, which determines whether one
+    /// interrupt can preempt another, and a subpriority field. For group 0 the group priority field
+    /// is bits `[7:binary_point + 1]`, while for group 1 it is bits `[7:binary_point]`. Values
+    /// below the minimum supported by the implementation are increased to the minimum.
+    ///
+    /// The group 1 binary point is ignored if `GICC_CTLR.CBPR` is set.
+    pub fn set_binary_point(&mut self, group: Group, binary_point: u8) {
+        assert!(binary_point < 8);
+        match group {
+            Group::Group0 => field!(self.gicc, bpr).write(binary_point.into()),
+            Group::Group1 => field!(self.gicc, abpr).write(binary_point.into()),
+        }
+    }
+
+    /// Returns the number of priority bits implemented by the GIC.
+    ///
+    /// This is found by temporarily setting the priority of SGI 0 on the current CPU interface to
+    /// 0xff and reading back which bits were implemented.
+    pub fn priority_bits(&mut self) -> u32 {
+        let mut ipriorityr = field!(self.gicd, ipriorityr);
+        let mut register = ipriorityr.get(0).unwrap();
+        let old = register.read();
+        register.write(old | 0xff);
+        let implemented = register.read() & 0xff;
+        register.write(old);
+        implemented.count_ones()
+    }
+
+    /// Returns the number of preemption levels available to interrupts of the given group on the
+    /// current CPU interface, given its current binary point and the implemented priority bits.
+    ///
+    /// If `GICC_CTLR.CBPR` is set then the group 0 binary point applies to both groups.
+    pub fn preemption_levels(&mut self, group: Group) -> u32 {
+        let group_priority_lsb =
+            if group == Group::Group1 && !self.cpu_interface_control().contains(GiccCtlr::CBPR) {
+                u32::from(self.binary_point(Group::Group1))
+            } else {
+                u32::from(self.binary_point(Group::Group0)) + 1
+            };
+        preemption_levels(self.priority_bits(), group_priority_lsb)
+    }
+
+    /// Returns the running priority of the current CPU interface, from `GICC_RPR`.
+    ///
+    /// This is the priority of the highest priority active interrupt, or 0xff if there is no active
+    /// interrupt.
+    pub fn running_priority(&self) -> u8 {
+        field_shared!(self.gicc, rpr).read() as u8
+    }
+
+    /// Returns the value of the active priorities register `GICC_APR<n>`.
+    ///
+    /// The format of these registers is implementation defined, but they can be saved and later
+    /// restored with [`GicV2::set_active_priorities`].
+    ///
+    /// Panics if `n` is greater than 3.
+    pub fn active_priorities(&self, n: usize) -> u32 {
+        field_shared!(self.gicc, apr).get(n).unwrap().read()
+    }
+
+    /// Restores the value of the active priorities register `GICC_APR<n>`, previously returned by
+    /// [`GicV2::active_priorities`].
+    ///
+    /// Panics if `n` is greater than 3.
+    pub fn set_active_priorities(&mut self, n: usize, value: u32) {
+        field!(self.gicc, apr).get(n).unwrap().write(value);
+    }
+
+    /// Returns the value of the non-secure active priorities register `GICC_NSAPR<n>`.
+    ///
+    /// Panics if `n` is greater than 3.
+    pub fn nonsecure_active_priorities(&self, n: usize) -> u32 {
+        field_shared!(self.gicc, nsapr).get(n).unwrap().read()
+    }
+
+    /// Restores the value of the non-secure active priorities register `GICC_NSAPR<n>`, previously
+    /// returned by [`GicV2::nonsecure_active_priorities`].
+    ///
+    /// Panics if `n` is greater than 3.
+    pub fn set_nonsecure_active_priorities(&mut self, n: usize, value: u32) {
+        field!(self.gicc, nsapr).get(n).unwrap().write(value);
+    }
+}
+
+/// The configuration of an interrupt, as returned by [`GicV2::interrupt_config`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InterruptConfig {
+    /// Whether the interrupt is enabled.
+    pub enabled: bool,
+    /// The priority of the interrupt.
+    pub priority: u8,
+    /// The trigger type of the interrupt.
+    pub trigger: Trigger,
+    /// The group of the interrupt.
+    pub group: Group,
+    /// The mask of CPU interfaces to which the interrupt is forwarded.
+    pub targets: u8,
+}
+
+/// An interrupt group.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Group {
+    /// Group 0, which may be signalled as FIQ and is secure if the GIC has the security extensions.
+    Group0,
+    /// Group 1, which is non-secure if the GIC has the security extensions.
+    Group1,
+}
+
+/// Which copy of the banked `GICC_CTLR` register the GIC is accessed through.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GiccCtlrView {
+    /// The secure copy, or the only copy in a GIC without the security extensions.
+    Secure,
+    /// The non-secure copy, in a GIC with the security extensions.
+    NonSecure,
 }
 
 /// The target specification for a software-generated interrupt.
@@ -192,3 +569,157 @@ pub enum SgiTargetListFilter {
     ForwardOthersOnly,
     ForwardSelfOnly,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::boxed::Box;
+
+    /// Zeroed memory standing in for the distributor and CPU interface registers of a GIC.
+    struct FakeGic {
+        gicd: Box<Gicd>,
+        gicc: Box<Gicc>,
+    }
+
+    impl FakeGic {
+        fn new() -> Self {
+            // SAFETY: Both register blocks consist only of integers, for which zero is valid.
+            unsafe {
+                Self {
+                    gicd: Box::new_zeroed().assume_init(),
+                    gicc: Box::new_zeroed().assume_init(),
+                }
+            }
+        }
+
+        /// Returns a driver instance using the fake registers.
+        fn gic(&mut self) -> GicV2<'_> {
+            // SAFETY: The fake registers are valid and not otherwise accessed while the driver
+            // exists.
+            unsafe { GicV2::new(&raw mut *self.gicd, &raw mut *self.gicc) }
+        }
+    }
+
+    #[test]
+    fn pending_and_active() {
+        let mut fake = FakeGic::new();
+        // The banked GICD_ITARGETSR<n> for SGIs and PPIs identify the current CPU interface.
+        fake.gicd.itargetsr[0].0 = 1 << 2;
+        // Other interrupts covered by the same registers are already pending or active.
+        fake.gicd.ispendr[1].0 = 0xff00;
+        fake.gicd.icactiver[0].0 = 0xff00;
+        let mut gic = fake.gic();
+
+        gic.set_pending(IntId::sgi(3));
+        gic.clear_pending(IntId::sgi(4));
+        gic.set_pending(IntId::spi(1));
+        gic.clear_active(IntId::ppi(2));
+        assert!(gic.is_pending(IntId::spi(1)));
+
+        assert_eq!(fake.gicd.spendsgir[3].0, 1 << 2);
+        assert_eq!(fake.gicd.cpendsgir[4].0, 0xff);
+        // Only the bit for the given interrupt is written, so other interrupts are unaffected.
+        assert_eq!(fake.gicd.ispendr[1].0, 1 << 1);
+        assert_eq!(fake.gicd.icactiver[0].0, 1 << 18);
+        assert_eq!(fake.gicd.ispendr[0].0, 0);
+    }
+
+    #[test]
+    fn interrupt_config() {
+        let mut fake = FakeGic::new();
+        fake.gicd.itargetsr[..32]
+            .iter_mut()
+            .for_each(|targets| targets.0 = 1 << 3);
+        let mut gic = fake.gic();
+
+        gic.set_interrupt_priority(IntId::spi(2), 0x50);
+        gic.set_trigger(IntId::spi(2), Trigger::Edge);
+        gic.set_group(IntId::spi(2), Group::Group1);
+        gic.set_targets(IntId::spi(2), 0b110).unwrap();
+        gic.enable_interrupt(IntId::spi(2), true).unwrap();
+
+        assert_eq!(
+            gic.interrupt_config(IntId::spi(2)),
+            InterruptConfig {
+                enabled: true,
+                priority: 0x50,
+                trigger: Trigger::Edge,
+                group: Group::Group1,
+                targets: 0b110,
+            }
+        );
+        // SGIs and PPIs report the current CPU interface from the banked registers.
+        assert_eq!(gic.targets(IntId::sgi(1)), 1 << 3);
+        assert_eq!(gic.targets(IntId::ppi(4)), 1 << 3);
+        assert_eq!(gic.interrupt_config(IntId::ppi(4)).targets, 1 << 3);
+        assert_eq!(gic.current_cpu_interface(), Some(3));
+    }
+
+    #[test]
+    fn groups() {
+        let mut fake = FakeGic::new();
+        fake.gicd.igroupr[1].0 = 0xff00;
+        let mut gic = fake.gic();
+
+        gic.enable_group0(true);
+        gic.enable_group1(true);
+        gic.enable_group0(false);
+        assert_eq!(fake.gicd.ctlr.0, GicdCtlr::EnableGrp1);
+
+        let mut gic = fake.gic();
+        gic.set_group(IntId::spi(1), Group::Group1);
+        gic.set_group(IntId::spi(9), Group::Group0);
+        assert_eq!(gic.group(IntId::spi(1)), Group::Group1);
+        assert_eq!(gic.group(IntId::spi(9)), Group::Group0);
+        assert_eq!(gic.group(IntId::spi(10)), Group::Group1);
+        // Other interrupts in the same register keep their group.
+        assert_eq!(fake.gicd.igroupr[1].0, 0xfd02);
+    }
+
+    #[test]
+    fn split_eoi() {
+        let mut fake = FakeGic::new();
+        fake.gicc.ctlr.0 = GiccCtlr::EnableGrp0 | GiccCtlr::EnableGrp1;
+        let mut gic = fake.gic();
+
+        gic.enable_split_eoi(true, GiccCtlrView::Secure);
+        gic.deactivate_interrupt(IntId::spi(3));
+        assert_eq!(
+            fake.gicc.ctlr.0,
+            GiccCtlr::EnableGrp0 | GiccCtlr::EnableGrp1 | GiccCtlr::EOImodeS | GiccCtlr::EOImodeNS
+        );
+        assert_eq!(fake.gicc.dir.0, 35);
+
+        let mut gic = fake.gic();
+        gic.enable_split_eoi(false, GiccCtlrView::Secure);
+        assert_eq!(
+            fake.gicc.ctlr.0,
+            GiccCtlr::EnableGrp0 | GiccCtlr::EnableGrp1
+        );
+
+        // In the non-secure copy bit 9 is EOImodeNS.
+        fake.gicc.ctlr.0 = GiccCtlr::EnableGrp0;
+        fake.gic().enable_split_eoi(true, GiccCtlrView::NonSecure);
+        assert_eq!(fake.gicc.ctlr.0, GiccCtlr::EnableGrp0 | GiccCtlr::EOImodeS);
+    }
+
+    #[test]
+    fn binary_point() {
+        let mut fake = FakeGic::new();
+        let mut gic = fake.gic();
+
+        gic.set_binary_point(Group::Group0, 2);
+        gic.set_binary_point(Group::Group1, 5);
+        assert_eq!(gic.binary_point(Group::Group0), 2);
+        assert_eq!(gic.binary_point(Group::Group1), 5);
+        // All 8 priority bits of the fake registers are implemented.
+        assert_eq!(gic.preemption_levels(Group::Group0), 32);
+        assert_eq!(gic.preemption_levels(Group::Group1), 8);
+        assert_eq!(fake.gicc.bpr.0, 2);
+        assert_eq!(fake.gicc.abpr.0, 5);
+
+        // With CBPR set the group 0 binary point applies to group 1 too.
+        fake.gicc.ctlr.0 = GiccCtlr::CBPR;
+        assert_eq!(fake.gic().preemption_levels(Group::Group1), 32);
+    }
+}
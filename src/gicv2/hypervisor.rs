@@ -0,0 +1,313 @@
+// Copyright 2026 The arm-gic Authors.
+// This project is dual-licensed under Apache 2.0 and MIT terms.
+// See LICENSE-APACHE and LICENSE-MIT for details.
+
+//! Driver for the GICv2 virtual interface control registers, for use by a hypervisor.
+//!
+//! Virtual interrupts are presented to a VM through the list registers in the GICH frame, and the
+//! VM accesses them through the GICV frame, which has the same layout as the physical CPU
+//! interface.
+
+use super::registers::{Gich, GichHcr, GichMisr, GichVtr};
+use crate::IntId;
+pub use crate::hypervisor::{
+    EoiedInterrupts, HypervisorError, ListRegisterState, VirtualInterrupt,
+};
+use core::ptr::NonNull;
+use safe_mmio::{UniqueMmioPointer, field, field_shared};
+
+/// The result of handling a maintenance interrupt with [`GicV2Hypervisor::handle_maintenance`].
+pub type Maintenance = crate::hypervisor::Maintenance<GichMisr>;
+
+/// A list register value.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct ListRegister(u32);
+
+impl ListRegister {
+    const HW: u32 = 1 << 31;
+    const GROUP1: u32 = 1 << 30;
+    const STATE_SHIFT: u32 = 28;
+    const PRIORITY_SHIFT: u32 = 23;
+    const EOI: u32 = 1 << 19;
+    const PHYSICAL_ID_SHIFT: u32 = 10;
+    const CPUID_MASK: u32 = 0b111;
+    const ID_MASK: u32 = 0x3ff;
+
+    /// An empty list register.
+    pub const INVALID: Self = Self(0);
+
+    /// Returns a list register value for the given virtual interrupt in the pending state.
+    ///
+    /// If the virtual interrupt isn't linked to a physical interrupt then a maintenance interrupt
+    /// is requested when it is deactivated, so that [`GicV2Hypervisor::handle_maintenance`] can
+    /// report it.
+    pub fn pending(interrupt: VirtualInterrupt) -> Self {
+        let mut value = ((ListRegisterState::Pending as u32) << Self::STATE_SHIFT)
+            | (u32::from(interrupt.priority >> 3) << Self::PRIORITY_SHIFT)
+            | (interrupt.intid.0 & Self::ID_MASK);
+        if interrupt.group1 {
+            value |= Self::GROUP1;
+        }
+        if let Some(physical) = interrupt.physical {
+            value |= Self::HW | ((physical.0 & Self::ID_MASK) << Self::PHYSICAL_ID_SHIFT);
+        } else {
+            value |= Self::EOI;
+            if interrupt.intid.is_sgi() {
+                value |=
+                    (u32::from(interrupt.source_cpu) & Self::CPUID_MASK) << Self::PHYSICAL_ID_SHIFT;
+            }
+        }
+        Self(value)
+    }
+
+    /// Returns the raw value of the list register.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns the state of the virtual interrupt.
+    pub fn state(self) -> ListRegisterState {
+        ListRegisterState::from_bits(self.0 >> Self::STATE_SHIFT)
+    }
+
+    /// Returns the virtual interrupt ID.
+    pub fn intid(self) -> IntId {
+        IntId(self.0 & Self::ID_MASK)
+    }
+
+    /// Returns the priority of the virtual interrupt.
+    pub fn priority(self) -> u8 {
+        (((self.0 >> Self::PRIORITY_SHIFT) & 0b11111) << 3) as u8
+    }
+
+    /// Returns whether the virtual interrupt is in group 1.
+    pub fn group1(self) -> bool {
+        self.0 & Self::GROUP1 != 0
+    }
+
+    /// Returns the physical interrupt which the virtual interrupt is linked to, if any.
+    pub fn physical(self) -> Option<IntId> {
+        if self.0 & Self::HW != 0 {
+            Some(IntId((self.0 >> Self::PHYSICAL_ID_SHIFT) & Self::ID_MASK))
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether a maintenance interrupt is requested when the virtual interrupt is
+    /// deactivated.
+    pub fn eoi_maintenance(self) -> bool {
+        self.0 & Self::HW == 0 && self.0 & Self::EOI != 0
+    }
+}
+
+/// Driver for the virtual interface control registers of the current CPU core.
+#[derive(Debug)]
+pub struct GicV2Hypervisor<'a> {
+    gich: UniqueMmioPointer<'a, Gich>,
+    /// The number of list registers implemented.
+    list_register_count: usize,
+}
+
+impl GicV2Hypervisor<'_> {
+    /// Constructs a new instance of the driver for the virtual interface control registers at the
+    /// given base address, and clears all list registers.
+    ///
+    /// # Safety
+    ///
+    /// The given base address must point to the GIC virtual interface control registers for the
+    /// current CPU core. This region must be mapped into the address space of the process as device
+    /// memory, and not have any other aliases, either via another instance of this driver or
+    /// otherwise.
+    pub unsafe fn new(gich: *mut Gich) -> Self {
+        // SAFETY: Our caller promises that the `gich` pointer satisfies the requirements of
+        // `UniqueMmioPointer::new`.
+        let gich = unsafe { UniqueMmioPointer::new(NonNull::new(gich).unwrap()) };
+        let list_register_count = field_shared!(gich, vtr).read().list_register_count();
+        let mut hypervisor = Self {
+            gich,
+            list_register_count,
+        };
+        hypervisor.clear();
+        hypervisor
+    }
+
+    /// Returns information about what the virtual interface supports.
+    pub fn vtr(&self) -> GichVtr {
+        field_shared!(self.gich, vtr).read()
+    }
+
+    /// Returns the number of list registers implemented.
+    pub fn list_register_count(&self) -> usize {
+        self.list_register_count
+    }
+
+    /// Returns the current value of the hypervisor control register.
+    pub fn hcr(&self) -> GichHcr {
+        field_shared!(self.gich, hcr).read()
+    }
+
+    /// Sets the hypervisor control register.
+    pub fn set_hcr(&mut self, hcr: GichHcr) {
+        field!(self.gich, hcr).write(hcr);
+    }
+
+    /// Enables or disables the virtual CPU interface, by setting `GICH_HCR.En`.
+    pub fn enable(&mut self, enable: bool) {
+        let hcr = self.hcr();
+        self.set_hcr(if enable {
+            hcr | GichHcr::En
+        } else {
+            hcr - GichHcr::En
+        });
+    }
+
+    /// Returns the virtual machine control register, which holds the state of the VM's virtual CPU
+    /// interface.
+    pub fn vmcr(&self) -> u32 {
+        field_shared!(self.gich, vmcr).read()
+    }
+
+    /// Sets the virtual machine control register.
+    pub fn set_vmcr(&mut self, vmcr: u32) {
+        field!(self.gich, vmcr).write(vmcr);
+    }
+
+    /// Returns the active priorities register.
+    pub fn apr(&self) -> u32 {
+        field_shared!(self.gich, apr).read()
+    }
+
+    /// Sets the active priorities register.
+    pub fn set_apr(&mut self, apr: u32) {
+        field!(self.gich, apr).write(apr);
+    }
+
+    /// Returns the value of the list register with the given index.
+    pub fn list_register(&self, n: usize) -> ListRegister {
+        assert!(n < self.list_register_count);
+        ListRegister(field_shared!(self.gich, lr).get(n).unwrap().read())
+    }
+
+    /// Sets the list register with the given index.
+    pub fn set_list_register(&mut self, n: usize, value: ListRegister) {
+        assert!(n < self.list_register_count);
+        field!(self.gich, lr).get(n).unwrap().write(value.0);
+    }
+
+    /// Invalidates all list registers.
+    pub fn clear(&mut self) {
+        for n in 0..self.list_register_count {
+            self.set_list_register(n, ListRegister::INVALID);
+        }
+    }
+
+    /// Returns a bitmask of the list registers with bits set in the given pair of status registers.
+    fn status_mask(&self, registers: [u32; 2]) -> u64 {
+        let mask = u64::from(registers[0]) | (u64::from(registers[1]) << 32);
+        mask & (u64::MAX >> (64 - self.list_register_count))
+    }
+
+    /// Returns a bitmask of list registers which don't contain a valid interrupt.
+    fn empty_mask(&self) -> u64 {
+        let elrsr = field_shared!(self.gich, elrsr);
+        self.status_mask([elrsr.get(0).unwrap().read(), elrsr.get(1).unwrap().read()])
+    }
+
+    /// Queues the given virtual interrupt as pending in a free list register, and returns the
+    /// index of the list register used.
+    ///
+    /// Returns an error if all list registers are in use, or if the virtual interrupt is already in
+    /// a list register.
+    pub fn inject(&mut self, interrupt: VirtualInterrupt) -> Result<usize, HypervisorError> {
+        if let Some(physical) = interrupt.physical
+            && (physical.is_sgi() || physical.0 >= IntId::SPECIAL_START)
+        {
+            return Err(HypervisorError::InvalidPhysicalInterrupt(physical));
+        }
+
+        let empty = self.empty_mask();
+        for n in 0..self.list_register_count {
+            if empty & (1 << n) == 0 && self.list_register(n).intid() == interrupt.intid {
+                return Err(HypervisorError::AlreadyQueued(interrupt.intid));
+            }
+        }
+        if empty == 0 {
+            return Err(HypervisorError::NoFreeListRegister);
+        }
+
+        let n = empty.trailing_zeros() as usize;
+        self.set_list_register(n, ListRegister::pending(interrupt));
+        Ok(n)
+    }
+
+    /// Handles a maintenance interrupt.
+    ///
+    /// List registers whose interrupts have been deactivated by the VM are invalidated, and the
+    /// corresponding virtual interrupt IDs returned along with the reasons for the maintenance
+    /// interrupt.
+    pub fn handle_maintenance(&mut self) -> Maintenance {
+        let misr = field_shared!(self.gich, misr).read();
+        let eisr = field_shared!(self.gich, eisr);
+        let eisr = self.status_mask([eisr.get(0).unwrap().read(), eisr.get(1).unwrap().read()]);
+
+        let mut intids = [IntId(0); 64];
+        for (n, intid) in intids.iter_mut().enumerate().take(self.list_register_count) {
+            if eisr & (1 << n) != 0 {
+                *intid = self.list_register(n).intid();
+                self.set_list_register(n, ListRegister::INVALID);
+            }
+        }
+
+        Maintenance {
+            misr,
+            eoied: EoiedInterrupts { mask: eisr, intids },
+        }
+    }
+
+    /// Returns a pointer to the virtual interface control registers.
+    ///
+    /// This may be used to read and write the registers directly for functionality not yet
+    /// supported by this driver.
+    pub fn gich_ptr(&mut self) -> UniqueMmioPointer<'_, Gich> {
+        self.gich.reborrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_register_pending() {
+        let lr = ListRegister::pending(VirtualInterrupt {
+            intid: IntId::sgi(2),
+            priority: 0xa0,
+            group1: true,
+            physical: None,
+            source_cpu: 3,
+        });
+        assert_eq!(lr.bits(), 0x5a08_0c02);
+        assert_eq!(lr.state(), ListRegisterState::Pending);
+        assert_eq!(lr.intid(), IntId::sgi(2));
+        assert_eq!(lr.priority(), 0xa0);
+        assert!(lr.group1());
+        assert_eq!(lr.physical(), None);
+        assert!(lr.eoi_maintenance());
+    }
+
+    #[test]
+    fn list_register_hardware() {
+        let lr = ListRegister::pending(VirtualInterrupt {
+            intid: IntId::spi(5),
+            priority: 0x80,
+            group1: false,
+            physical: Some(IntId::spi(10)),
+            source_cpu: 0,
+        });
+        assert_eq!(lr.bits(), 0x9800_a825);
+        assert_eq!(lr.physical(), Some(IntId::spi(10)));
+        assert!(!lr.eoi_maintenance());
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright 2026 The arm-gic Authors.
+// This project is dual-licensed under Apache 2.0 and MIT terms.
+// See LICENSE-APACHE and LICENSE-MIT for details.
+
+//! Driver for a GICv2m MSI frame, which allows devices to signal SPIs by writing to a doorbell
+//! register.
+//!
+//! Each frame covers a contiguous range of SPIs, described by `MSI_TYPER`. [`GicV2m`] allocates
+//! SPIs from this range and returns the [`MsiMessage`] which a device such as a PCIe endpoint
+//! should write to signal each of them.
+
+use super::GicV2;
+use crate::{IntId, MsiMessage, Trigger};
+use core::{mem::offset_of, ptr::NonNull};
+use safe_mmio::{
+    UniqueMmioPointer, field_shared,
+    fields::{ReadPure, WriteOnly},
+};
+use thiserror::Error;
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+/// An error which may be returned from operations on a GICv2m MSI frame.
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MsiError {
+    #[error("No free SPIs in the MSI frame")]
+    NoFreeSpi,
+    #[error("{0:?} is not in the range of SPIs covered by the MSI frame")]
+    NotInFrame(IntId),
+    #[error("{0:?} has not been allocated")]
+    NotAllocated(IntId),
+}
+
+/// MSI type register value.
+#[derive(Clone, Copy, Debug, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+#[repr(transparent)]
+pub struct MsiTyper(u32);
+
+impl MsiTyper {
+    /// Returns the first SPI covered by the frame.
+    pub fn base_spi(self) -> IntId {
+        IntId((self.0 >> 16) & 0x3ff)
+    }
+
+    /// Returns the number of SPIs covered by the frame.
+    pub fn spi_count(self) -> u32 {
+        self.0 & 0x3ff
+    }
+}
+
+/// GICv2m MSI frame registers.
+#[repr(C, align(8))]
+pub struct MsiFrame {
+    _reserved_0: [u32; 2],
+    /// MSI Type Register.
+    pub typer: ReadPure<MsiTyper>,
+    _reserved_1: [u32; 0xD],
+    /// Set SPI Register, written by devices to signal an SPI.
+    pub setspi_ns: WriteOnly<u32>,
+    _reserved_2: [u32; 0x3E2],
+    /// Interface Identification Register.
+    pub iidr: ReadPure<u32>,
+    _reserved_3: [u32; 0xC],
+}
+
+/// Driver for a GICv2m MSI frame, with an allocator for the SPIs which it covers.
+#[derive(Debug)]
+pub struct GicV2m<'a> {
+    frame: UniqueMmioPointer<'a, MsiFrame>,
+    /// The physical address of the frame, as seen by devices.
+    physical_address: u64,
+    typer: MsiTyper,
+    /// One bit for each SPI covered by the frame, set if it has been allocated.
+    allocated: [u32; 32],
+}
+
+impl<'a> GicV2m<'a> {
+    /// Constructs a new instance of the driver for the MSI frame at the given address, which has
+    /// the given physical address as seen by devices.
+    ///
+    /// # Safety
+    ///
+    /// The given pointer must point to the GICv2m MSI frame registers. This region must be mapped
+    /// into the address space of the process as device memory, and not have any other aliases,
+    /// either via another instance of this driver or otherwise.
+    pub unsafe fn new(frame: *mut MsiFrame, physical_address: u64) -> Self {
+        // SAFETY: Our caller promises that the `frame` pointer satisfies the requirements of
+        // `UniqueMmioPointer::new`.
+        let frame = unsafe { UniqueMmioPointer::new(NonNull::new(frame).unwrap()) };
+        Self::from_frame(frame, physical_address)
+    }
+
+    fn from_frame(frame: UniqueMmioPointer<'a, MsiFrame>, physical_address: u64) -> Self {
+        let typer = field_shared!(frame, typer).read();
+        Self {
+            frame,
+            physical_address,
+            typer,
+            allocated: [0; 32],
+        }
+    }
+
+    /// Returns the range of SPIs covered by the frame.
+    pub fn typer(&self) -> MsiTyper {
+        self.typer
+    }
+
+    /// Returns the value of the frame's interface identification register.
+    pub fn iidr(&self) -> u32 {
+        field_shared!(self.frame, iidr).read()
+    }
+
+    /// Returns the message which a device should write to signal the given SPI.
+    ///
+    /// Returns an error if the SPI is not covered by the frame.
+    pub fn message(&self, intid: IntId) -> Result<MsiMessage, MsiError> {
+        self.offset(intid)?;
+        Ok(MsiMessage {
+            address: self.physical_address + offset_of!(MsiFrame, setspi_ns) as u64,
+            data: intid.0,
+        })
+    }
+
+    /// Allocates a free SPI from the frame, configures it as edge-triggered, and returns it along
+    /// with the message which a device should write to signal it.
+    ///
+    /// The SPI must still be given a priority and target, and enabled, on the GIC.
+    pub fn allocate(&mut self, gic: &mut GicV2) -> Result<(IntId, MsiMessage), MsiError> {
+        let offset = (0..self.typer.spi_count())
+            .find(|&offset| self.allocated[offset as usize / 32] & (1 << (offset % 32)) == 0)
+            .ok_or(MsiError::NoFreeSpi)?;
+        self.allocated[offset as usize / 32] |= 1 << (offset % 32);
+
+        let intid = IntId(self.typer.base_spi().0 + offset);
+        gic.set_trigger(intid, Trigger::Edge);
+        Ok((intid, self.message(intid)?))
+    }
+
+    /// Frees the given SPI, which was previously returned by [`GicV2m::allocate`].
+    ///
+    /// The SPI should be disabled on the GIC first.
+    pub fn free(&mut self, intid: IntId) -> Result<(), MsiError> {
+        let offset = self.offset(intid)?;
+        let word = &mut self.allocated[offset as usize / 32];
+        let bit = 1 << (offset % 32);
+        if *word & bit == 0 {
+            return Err(MsiError::NotAllocated(intid));
+        }
+        *word &= !bit;
+        Ok(())
+    }
+
+    /// Returns the offset of the given SPI from the first SPI covered by the frame.
+    fn offset(&self, intid: IntId) -> Result<u32, MsiError> {
+        intid
+            .0
+            .checked_sub(self.typer.base_spi().0)
+            .filter(|&offset| offset < self.typer.spi_count())
+            .ok_or(MsiError::NotInFrame(intid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_frame(typer: u32) -> MsiFrame {
+        MsiFrame {
+            _reserved_0: [0; 2],
+            typer: ReadPure(MsiTyper(typer)),
+            _reserved_1: [0; 0xD],
+            setspi_ns: WriteOnly(0),
+            _reserved_2: [0; 0x3E2],
+            iidr: ReadPure(0),
+            _reserved_3: [0; 0xC],
+        }
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(offset_of!(MsiFrame, typer), 0x008);
+        assert_eq!(offset_of!(MsiFrame, setspi_ns), 0x040);
+        assert_eq!(offset_of!(MsiFrame, iidr), 0xFCC);
+        assert_eq!(size_of::<MsiFrame>(), 0x1000);
+    }
+
+    #[test]
+    fn typer() {
+        let typer = MsiTyper(0x0040_0040);
+        assert_eq!(typer.base_spi(), IntId::spi(32));
+        assert_eq!(typer.spi_count(), 64);
+    }
+
+    #[test]
+    fn message() {
+        let mut frame = fake_frame(0x0050_0002);
+        let msi = GicV2m::from_frame(UniqueMmioPointer::from(&mut frame), 0x2_0000);
+        assert_eq!(
+            msi.message(IntId::spi(49)),
+            Ok(MsiMessage {
+                address: 0x2_0040,
+                data: 81
+            })
+        );
+        assert_eq!(
+            msi.message(IntId::spi(50)),
+            Err(MsiError::NotInFrame(IntId::spi(50)))
+        );
+        assert_eq!(
+            msi.message(IntId::ppi(0)),
+            Err(MsiError::NotInFrame(IntId::ppi(0)))
+        );
+    }
+
+    #[test]
+    fn free() {
+        let mut frame = fake_frame(0x0050_0002);
+        let mut msi = GicV2m::from_frame(UniqueMmioPointer::from(&mut frame), 0x2_0000);
+        msi.allocated[0] = 0b10;
+        assert_eq!(
+            msi.free(IntId::spi(48)),
+            Err(MsiError::NotAllocated(IntId::spi(48)))
+        );
+        assert_eq!(msi.free(IntId::spi(49)), Ok(()));
+        assert_eq!(msi.allocated[0], 0);
+    }
+}
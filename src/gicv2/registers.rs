@@ -27,6 +27,42 @@ impl Debug for GicdCtlr {
     }
 }
 
+/// CPU interface control register value.
+///
+/// This is the layout of the secure copy of the register, or of the register in a GIC without the
+/// security extensions. The non-secure copy only controls group 1 interrupts: there bit 0 is
+/// `EnableGrp1`, bits 5 and 6 are `FIQBypDisGrp1` and `IRQBypDisGrp1`, and bit 9 is `EOImodeNS`,
+/// so they correspond to `EnableGrp0`, `FIQBypDisGrp0`, `IRQBypDisGrp0` and `EOImodeS` here. The
+/// other bits are reserved in the non-secure copy.
+#[derive(Clone, Copy, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+#[repr(transparent)]
+pub struct GiccCtlr(u32);
+
+bitflags! {
+    impl GiccCtlr: u32 {
+        const EOImodeNS = 1 << 10;
+        const EOImodeS = 1 << 9;
+        const IRQBypDisGrp1 = 1 << 8;
+        const FIQBypDisGrp1 = 1 << 7;
+        const IRQBypDisGrp0 = 1 << 6;
+        const FIQBypDisGrp0 = 1 << 5;
+        const CBPR = 1 << 4;
+        const FIQEn = 1 << 3;
+        const AckCtl = 1 << 2;
+        const EnableGrp1 = 1 << 1;
+        const EnableGrp0 = 1 << 0;
+    }
+}
+
+impl Debug for GiccCtlr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "GiccCtlr(")?;
+        bitflags::parser::to_writer(self, &mut *f)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
 /// GICv2 type register value.
 #[derive(Clone, Copy, Debug, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
 #[repr(transparent)]
@@ -80,51 +116,176 @@ pub struct Gicd {
     pub icactiver: [ReadPureWrite<u32>; 0x20],
     /// Interrupt Priority Registers.
     pub ipriorityr: [ReadPureWrite<u32>; 0x100],
-    /// Interrupt Processor Targets Registers.
-    pub itargetsr: [u32; 0x100],
+    /// Interrupt Processor Targets Registers, accessed a byte at a time.
+    pub itargetsr: [ReadPureWrite<u8>; 0x400],
     /// Interrupt Configuration Registers.
     pub icfgr: [ReadPureWrite<u32>; 0x40],
     _reserved_1: [u32; 0x80],
     /// Software Generated Interrupt Register.
     pub sgir: WriteOnly<u32>,
+    _reserved_2: [u32; 3],
+    /// SGI Clear-Pending Registers, accessed a byte at a time.
+    pub cpendsgir: [ReadPureWrite<u8>; 0x10],
+    /// SGI Set-Pending Registers, accessed a byte at a time.
+    pub spendsgir: [ReadPureWrite<u8>; 0x10],
 }
 
 /// GIC CPU interface registers.
 #[repr(C, align(8))]
 pub struct Gicc {
     /// CPU Interface Control Register.
-    pub ctlr: ReadPureWrite<u32>,
+    pub ctlr: ReadPureWrite<GiccCtlr>,
     /// Interrupt Priority Mask Register.
     pub pmr: ReadPureWrite<u32>,
     /// Binary Point Register.
-    pub bpr: u32,
+    pub bpr: ReadPureWrite<u32>,
     /// Interrupt Acknowledge Register.
     pub iar: u32,
     /// End of Interrupt Register.
     pub eoir: u32,
     /// Running Priority Register.
-    pub rpr: u32,
+    pub rpr: ReadPure<u32>,
     /// Highest Priority Pending Interrupt Register.
     pub hppir: u32,
     /// Aliased Binary Point Register
-    pub abpr: u32,
+    pub abpr: ReadPureWrite<u32>,
     /// Aliased Interrupt Acknowledge Register
     pub aiar: ReadOnly<u32>,
     /// Aliased End of Interrupt Register
     pub aeoir: WriteOnly<u32>,
     /// Aliased Highest Priority Pending Interrupt Register
     pub ahppir: u32,
-    _reserved_0: [u32; 0x34],
+    _reserved_0: [u32; 0x29],
+    /// Active Priorities Registers.
+    pub apr: [ReadPureWrite<u32>; 4],
+    /// Non-secure Active Priorities Registers.
+    pub nsapr: [ReadPureWrite<u32>; 4],
+    _reserved_1: [u32; 3],
     /// CPU Interface Identification Register.
     pub iidr: u32,
-    _reserved_1: [u32; 0x3C0],
+    _reserved_2: [u32; 0x3C0],
     /// Deactivate Interrupt Register.
-    pub dir: u32,
+    pub dir: WriteOnly<u32>,
+}
+
+/// GIC virtual CPU interface registers.
+///
+/// These have the same layout as the physical CPU interface registers.
+pub type Gicv = Gicc;
+
+/// Virtual interface control register value.
+#[derive(Clone, Copy, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+#[repr(transparent)]
+pub struct GichHcr(u32);
+
+bitflags! {
+    impl GichHcr: u32 {
+        const VGrp1DIE = 1 << 7;
+        const VGrp1EIE = 1 << 6;
+        const VGrp0DIE = 1 << 5;
+        const VGrp0EIE = 1 << 4;
+        const NPIE = 1 << 3;
+        const LRENPIE = 1 << 2;
+        const UIE = 1 << 1;
+        const En = 1 << 0;
+    }
+}
+
+impl GichHcr {
+    /// Returns the number of EOIs which didn't match a list register, from the EOICount field.
+    pub fn eoi_count(self) -> u32 {
+        self.bits() >> 27
+    }
+}
+
+impl Debug for GichHcr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "GichHcr(")?;
+        bitflags::parser::to_writer(self, &mut *f)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+/// Maintenance interrupt status register value.
+#[derive(Clone, Copy, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+#[repr(transparent)]
+pub struct GichMisr(u32);
+
+bitflags! {
+    impl GichMisr: u32 {
+        const VGrp1D = 1 << 7;
+        const VGrp1E = 1 << 6;
+        const VGrp0D = 1 << 5;
+        const VGrp0E = 1 << 4;
+        const NP = 1 << 3;
+        const LRENP = 1 << 2;
+        const U = 1 << 1;
+        const EOI = 1 << 0;
+    }
+}
+
+impl Debug for GichMisr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "GichMisr(")?;
+        bitflags::parser::to_writer(self, &mut *f)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+/// VGIC type register value.
+#[derive(Clone, Copy, Debug, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+#[repr(transparent)]
+pub struct GichVtr(u32);
+
+impl GichVtr {
+    /// Returns the number of virtual priority bits implemented.
+    pub fn priority_bits(self) -> u32 {
+        (self.0 >> 29) + 1
+    }
+
+    /// Returns the number of virtual preemption bits implemented.
+    pub fn preemption_bits(self) -> u32 {
+        ((self.0 >> 26) & 0b111) + 1
+    }
+
+    /// Returns the number of list registers implemented.
+    pub fn list_register_count(self) -> usize {
+        ((self.0 & 0b111111) + 1) as usize
+    }
+}
+
+/// GIC virtual interface control registers.
+#[repr(C, align(8))]
+pub struct Gich {
+    /// Hypervisor Control Register.
+    pub hcr: ReadPureWrite<GichHcr>,
+    /// VGIC Type Register.
+    pub vtr: ReadPure<GichVtr>,
+    /// Virtual Machine Control Register.
+    pub vmcr: ReadPureWrite<u32>,
+    _reserved_0: u32,
+    /// Maintenance Interrupt Status Register.
+    pub misr: ReadPure<GichMisr>,
+    _reserved_1: [u32; 3],
+    /// End of Interrupt Status Registers.
+    pub eisr: [ReadPure<u32>; 2],
+    _reserved_2: [u32; 2],
+    /// Empty List Register Status Registers.
+    pub elrsr: [ReadPure<u32>; 2],
+    _reserved_3: [u32; 0x2E],
+    /// Active Priorities Register.
+    pub apr: ReadPureWrite<u32>,
+    _reserved_4: [u32; 3],
+    /// List Registers.
+    pub lr: [ReadPureWrite<u32>; 0x40],
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use core::mem::offset_of;
 
     #[test]
     fn cpu_count() {
@@ -138,4 +299,42 @@ mod tests {
         assert_eq!(Typer(0b00011).num_irqs(), 128);
         assert_eq!(Typer(0b11111).num_irqs(), 1024);
     }
+
+    #[test]
+    fn gicd_layout() {
+        assert_eq!(offset_of!(Gicd, ipriorityr), 0x400);
+        assert_eq!(offset_of!(Gicd, itargetsr), 0x800);
+        assert_eq!(offset_of!(Gicd, icfgr), 0xC00);
+        assert_eq!(offset_of!(Gicd, sgir), 0xF00);
+        assert_eq!(offset_of!(Gicd, cpendsgir), 0xF10);
+        assert_eq!(offset_of!(Gicd, spendsgir), 0xF20);
+    }
+
+    #[test]
+    fn gicc_layout() {
+        assert_eq!(offset_of!(Gicc, rpr), 0x14);
+        assert_eq!(offset_of!(Gicc, aeoir), 0x24);
+        assert_eq!(offset_of!(Gicc, apr), 0xD0);
+        assert_eq!(offset_of!(Gicc, nsapr), 0xE0);
+        assert_eq!(offset_of!(Gicc, iidr), 0xFC);
+        assert_eq!(offset_of!(Gicc, dir), 0x1000);
+    }
+
+    #[test]
+    fn gich_layout() {
+        assert_eq!(offset_of!(Gich, misr), 0x10);
+        assert_eq!(offset_of!(Gich, eisr), 0x20);
+        assert_eq!(offset_of!(Gich, elrsr), 0x30);
+        assert_eq!(offset_of!(Gich, apr), 0xF0);
+        assert_eq!(offset_of!(Gich, lr), 0x100);
+        assert_eq!(size_of::<Gich>(), 0x200);
+    }
+
+    #[test]
+    fn gich_vtr() {
+        let vtr = GichVtr(0x9000_0003);
+        assert_eq!(vtr.list_register_count(), 4);
+        assert_eq!(vtr.priority_bits(), 5);
+        assert_eq!(vtr.preemption_bits(), 5);
+    }
 }
@@ -4,23 +4,62 @@
 
 //! Driver for the Arm Generic Interrupt Controller version 3 (or 4).
 
+#[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+pub mod hypervisor;
+pub mod its;
 pub mod registers;
+pub mod vgic;
 
-use self::registers::{Gicd, GicdCtlr, Gicr, GicrCtlr, Sgi, Waker};
+use self::its::{CpuCollection, ITSError, Its};
+use self::registers::{Gicd, GicdCtlr, Gicr, GicrCtlr, GicrVlpi, MemoryAttributes, Sgi, Waker};
+#[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+use crate::preemption_levels;
+#[cfg(any(test, feature = "fakes", target_arch = "aarch64"))]
+use crate::sysreg::read_icc_nmiar1_el1;
 #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
 use crate::sysreg::{
-    read_icc_hppir0_el1, read_icc_hppir1_el1, read_icc_iar0_el1, read_icc_iar1_el1,
-    write_icc_asgi1r_el1, write_icc_ctlr_el1, write_icc_eoir0_el1, write_icc_eoir1_el1,
-    write_icc_igrpen0_el1, write_icc_igrpen1_el1, write_icc_pmr_el1, write_icc_sgi0r_el1,
-    write_icc_sgi1r_el1, write_icc_sre_el1,
+    read_icc_ap0r0_el1, read_icc_ap0r1_el1, read_icc_ap0r2_el1, read_icc_ap0r3_el1,
+    read_icc_ap1r0_el1, read_icc_ap1r1_el1, read_icc_ap1r2_el1, read_icc_ap1r3_el1,
+    read_icc_bpr0_el1, read_icc_bpr1_el1, read_icc_ctlr_el1, read_icc_hppir0_el1,
+    read_icc_hppir1_el1, read_icc_iar0_el1, read_icc_iar1_el1, read_icc_rpr_el1,
+    write_icc_ap0r0_el1, write_icc_ap0r1_el1, write_icc_ap0r2_el1, write_icc_ap0r3_el1,
+    write_icc_ap1r0_el1, write_icc_ap1r1_el1, write_icc_ap1r2_el1, write_icc_ap1r3_el1,
+    write_icc_asgi1r_el1, write_icc_bpr0_el1, write_icc_bpr1_el1, write_icc_ctlr_el1,
+    write_icc_dir_el1, write_icc_eoir0_el1, write_icc_eoir1_el1, write_icc_igrpen0_el1,
+    write_icc_igrpen1_el1, write_icc_pmr_el1, write_icc_sgi0r_el1, write_icc_sgi1r_el1,
+    write_icc_sre_el1,
 };
-use crate::{IntId, Trigger};
-use core::{hint::spin_loop, ptr::NonNull};
-use registers::{GicrIidr, GicrPwrr, GicrSgi, GicrTyper, Typer};
+use crate::{IntId, MsiMessage, Trigger, dsb_st};
+use core::{
+    fmt::{self, Debug, Formatter},
+    hint::spin_loop,
+    mem::offset_of,
+    ptr::NonNull,
+};
+#[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+use registers::IccCtlr;
+use registers::{GicrIidr, GicrPwrr, GicrSgi, GicrTyper, GitsBaser, Typer, Typer2};
 use safe_mmio::fields::ReadPureWrite;
 use safe_mmio::{SharedMmioPointer, UniqueMmioPointer, field, field_shared, split_fields};
 use thiserror::Error;
 
+/// An error which may be returned from operations on a GIC Distributor.
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GICDError {
+    #[error("Distributor doesn't support SGIs without an active state")]
+    NassgiNotSupported,
+    #[error("Distributor doesn't support 1 of N SPI routing")]
+    OneOfNNotSupported,
+    #[error("{0:?} is not an SPI")]
+    NotAnSpi(IntId),
+    #[error("Distributor doesn't support non-maskable interrupts")]
+    NmiNotSupported,
+    #[error("{0:?} is an LPI, which can't be a non-maskable interrupt")]
+    NmiNotSupportedForLpi(IntId),
+    #[error("Distributor doesn't support message-based SPIs")]
+    MessageBasedSpisNotSupported,
+}
+
 /// An error which may be returned from operations on a GIC Redistributor.
 #[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
 pub enum GICRError {
@@ -28,6 +67,36 @@ pub enum GICRError {
     AlreadyAwake,
     #[error("Redistributor has already been notified that the connected core is asleep")]
     AlreadyAsleep,
+    #[error("Redistributor doesn't support physical LPIs")]
+    LpisNotSupported,
+    #[error("LPIs are already enabled on the redistributor")]
+    LpisAlreadyEnabled,
+    #[error("No LPI configuration table has been provided")]
+    NoLpiConfigTable,
+    #[error("LPI table is too small or not correctly aligned")]
+    InvalidLpiTable,
+    #[error("Redistributor doesn't support the requested memory attributes")]
+    UnsupportedAttributes,
+    #[error("Redistributor doesn't support direct LPIs")]
+    DirectLpisNotSupported,
+    #[error("{0:?} is not an LPI")]
+    NotAnLpi(IntId),
+    #[error("Redistributor doesn't support virtual LPIs")]
+    VirtualLpisNotSupported,
+    #[error("A vPE is already resident on the redistributor")]
+    VpeAlreadyResident,
+    #[error("No vPE is resident on the redistributor")]
+    VpeNotResident,
+    #[error("Redistributor doesn't support GICv4.1")]
+    Gicv4_1NotSupported,
+    #[error("vPE table is not valid or can't be shared with the redistributor")]
+    InvalidVpeTable,
+    #[error("No vPE table has been set on the redistributor")]
+    NoVpeTable,
+    #[error("No ITS has been set to invalidate LPI configuration")]
+    NoIts,
+    #[error("ITS error: {0}")]
+    ITSError(#[from] ITSError),
 }
 
 /// Modifies `nth` bit of memory pointed by `registers`.
@@ -59,6 +128,38 @@ fn clear_bit(registers: UniqueMmioPointer<[ReadPureWrite<u32>]>, nth: usize) {
     modify_bit(registers, nth, false);
 }
 
+/// Returns whether `nth` bit of memory pointed by `registers` is set.
+fn get_bit(registers: SharedMmioPointer<[ReadPureWrite<u32>]>, nth: usize) -> bool {
+    registers.get(nth / 32).unwrap().read() & (1 << (nth % 32)) != 0
+}
+
+/// Returns the index and mask of each bitmap register covering interrupts `start..end`, where
+/// `start` is a multiple of 32.
+fn bitmap_registers(start: usize, end: usize) -> impl Iterator<Item = (usize, u32)> {
+    (start..end).step_by(32).map(move |i| {
+        let bits = if end - i >= 32 {
+            0xffffffff
+        } else {
+            (1 << (end - i)) - 1
+        };
+        (i / 32, bits)
+    })
+}
+
+/// Returns the offset of the given extended SPI from the start of the extended SPI range.
+fn espi_index(intid: IntId) -> usize {
+    (intid.0 - IntId::ESPI_START) as usize
+}
+
+/// One of the registers to set or clear the pending or active state of interrupts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum StateRegister {
+    SetPending,
+    ClearPending,
+    SetActive,
+    ClearActive,
+}
+
 /// Driver for an Arm Generic Interrupt Controller version 3 (or 4).
 #[derive(Debug)]
 pub struct GicV3<'a> {
@@ -68,6 +169,130 @@ pub struct GicV3<'a> {
     cpu_count: usize,
     /// The offset in bytes between the start of redistributor frames.
     gicr_stride: usize,
+    /// The LPI configuration table shared by all redistributors, if one has been provided.
+    lpi_config_table: Option<LpiConfigTable<'a>>,
+    /// The ITS to use for invalidating cached LPI configuration, if any.
+    its: Option<Its<'a>>,
+    /// The ITS collection for each CPU core, indexed by linear CPU index.
+    its_collections: &'a [CpuCollection],
+}
+
+/// The LPI configuration table, with the information needed to program `GICR_PROPBASER`.
+struct LpiConfigTable<'a> {
+    /// One byte for each LPI, with its priority and enable bit.
+    table: &'a mut [u8],
+    physical_address: u64,
+    attributes: MemoryAttributes,
+    /// The number of interrupt ID bits which the table covers.
+    id_bits: u32,
+}
+
+impl LpiConfigTable<'_> {
+    /// Returns the index of the given LPI in the table.
+    ///
+    /// Panics if the LPI is beyond the range of interrupt IDs covered by the table.
+    fn index(&self, intid: IntId) -> usize {
+        let index = (intid.0 - IntId::LPI_START) as usize;
+        assert!(
+            index < self.table.len(),
+            "{intid:?} is beyond the range covered by the LPI configuration table"
+        );
+        index
+    }
+}
+
+impl Debug for LpiConfigTable<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("LpiConfigTable")
+            .field("len", &self.table.len())
+            .field("physical_address", &self.physical_address)
+            .field("attributes", &self.attributes)
+            .field("id_bits", &self.id_bits)
+            .finish()
+    }
+}
+
+/// The tables for a GICv4 virtual PE, and its state while not resident on a redistributor.
+///
+/// This is passed to [`GicV3::make_vpe_resident`] and [`GicV3::make_vpe_non_resident`] to schedule
+/// the vPE on a CPU core.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VirtualPe {
+    config_table_physical_address: u64,
+    id_bits: u32,
+    pending_table_physical_address: u64,
+    attributes: MemoryAttributes,
+    /// Whether the implementation defined area of the pending table is invalid.
+    idai: bool,
+    /// Whether the vPE may have pending interrupts.
+    pending_last: bool,
+    /// Whether the pending table is still zeroed, because the vPE has never been resident.
+    pending_table_zeroed: bool,
+    /// The GICv4.1 default doorbell LPI.
+    default_doorbell: Option<IntId>,
+    /// Whether the vPE has virtual group 0 interrupts enabled, for GICv4.1.
+    group0_enabled: bool,
+    /// Whether the vPE has virtual group 1 interrupts enabled, for GICv4.1.
+    group1_enabled: bool,
+}
+
+impl VirtualPe {
+    /// Creates a new vPE which has never been resident, with the given virtual LPI configuration
+    /// and pending tables.
+    ///
+    /// `id_bits` is the number of virtual interrupt ID bits which the configuration table covers.
+    /// The configuration table must be aligned to 4 KiB, or to 64 KiB on GICv4.1, and the pending
+    /// table to 64 KiB. The pending table must initially be zeroed.
+    pub fn new(
+        config_table_physical_address: u64,
+        id_bits: u32,
+        pending_table_physical_address: u64,
+        attributes: MemoryAttributes,
+    ) -> Self {
+        Self {
+            config_table_physical_address,
+            id_bits,
+            pending_table_physical_address,
+            attributes,
+            idai: true,
+            pending_last: true,
+            pending_table_zeroed: true,
+            default_doorbell: None,
+            group0_enabled: true,
+            group1_enabled: true,
+        }
+    }
+
+    /// Sets the default doorbell LPI of the vPE on GICv4.1, which is generated when any of its
+    /// virtual interrupts becomes pending while it is not resident.
+    ///
+    /// The ITS is given the default doorbell when the vPE is mapped, so this must be set before
+    /// then.
+    pub fn set_default_doorbell(&mut self, doorbell: Option<IntId>) {
+        self.default_doorbell = doorbell;
+    }
+
+    /// Sets whether the vPE has virtual group 0 and group 1 interrupts enabled on GICv4.1, which
+    /// the redistributor uses to decide whether its pending interrupts can be signalled.
+    ///
+    /// This takes effect the next time the vPE is made resident. Both are initially enabled.
+    pub fn set_groups_enabled(&mut self, group0: bool, group1: bool) {
+        self.group0_enabled = group0;
+        self.group1_enabled = group1;
+    }
+
+    /// Returns whether the vPE's pending table is still zeroed, because the vPE has never been
+    /// made resident.
+    pub fn pending_table_zeroed(&self) -> bool {
+        self.pending_table_zeroed
+    }
+
+    /// Returns whether the vPE had pending interrupts when it was last made non-resident.
+    ///
+    /// A hypervisor can use this to decide whether to schedule the vPE.
+    pub fn pending_last(&self) -> bool {
+        self.pending_last
+    }
 }
 
 fn get_redistributor_window_size(gicr_base: *mut GicrSgi, gic_v4: bool) -> usize {
@@ -94,7 +319,26 @@ fn get_redistributor_window_size(gicr_base: *mut GicrSgi, gic_v4: bool) -> usize
     size_of::<GicrSgi>()
 }
 
-impl GicV3<'_> {
+impl<'a> GicV3<'a> {
+    /// The priority with which LPIs are initially configured.
+    const LPI_DEFAULT_PRIORITY: u8 = 0xa0;
+    /// Bit 0 of each LPI configuration table entry enables the LPI.
+    const LPI_CONFIG_ENABLE: u8 = 1 << 0;
+    /// Bit 1 of each LPI configuration table entry is RES1.
+    const LPI_CONFIG_RES1: u8 = 1 << 1;
+    const GICR_PENDBASER_PTZ: u64 = 1 << 62;
+    const GICR_SYNCR_BUSY: u32 = 1 << 0;
+    const GICR_VPENDBASER_VALID: u64 = 1 << 63;
+    const GICR_VPENDBASER_IDAI: u64 = 1 << 62;
+    const GICR_VPENDBASER_PENDING_LAST: u64 = 1 << 61;
+    const GICR_VPENDBASER_DIRTY: u64 = 1 << 60;
+    /// In the GICv4.1 layout, requests the default doorbell when the vPE is made non-resident.
+    const GICR_VPENDBASER_4_1_DB: u64 = 1 << 62;
+    const GICR_VPENDBASER_4_1_VGRP0EN: u64 = 1 << 59;
+    const GICR_VPENDBASER_4_1_VGRP1EN: u64 = 1 << 58;
+    const GICR_VPROPBASER_4_1_VALID: u64 = 1 << 63;
+    const GICR_VSGIPENDR_BUSY: u32 = 1 << 31;
+
     /// Constructs a new instance of the driver for a GIC with the given distributor and
     /// redistributor base addresses.
     ///
@@ -117,6 +361,9 @@ impl GicV3<'_> {
             gicr_base,
             cpu_count,
             gicr_stride: get_redistributor_window_size(gicr_base, gic_v4),
+            lpi_config_table: None,
+            its: None,
+            its_collections: &[],
         }
     }
 
@@ -140,8 +387,10 @@ impl GicV3<'_> {
 
         // Disable use of `ICC_PMR_EL1` as a hint for interrupt distribution, configure a write to
         // an EOI register to also deactivate the interrupt, and configure preemption groups for
-        // group 0 and group 1 interrupts separately.
-        write_icc_ctlr_el1(0);
+        // group 0 and group 1 interrupts separately. Leave any other fields unchanged.
+        Self::set_cpu_interface_control(
+            Self::cpu_interface_control() - (IccCtlr::PMHE | IccCtlr::EOImode | IccCtlr::CBPR),
+        );
     }
 
     /// Initialises the GIC and marks the given CPU core as awake.
@@ -155,21 +404,23 @@ impl GicV3<'_> {
         field!(self.gicd, ctlr).write(GicdCtlr::ARE_S | GicdCtlr::EnableGrp1NS);
 
         {
-            // Put all SGIs and PPIs into non-secure group 1.
+            // Put all SGIs, PPIs and extended PPIs into non-secure group 1.
             for cpu in 0..self.cpu_count {
+                let eppis = self.gicr_typer(cpu).max_eppi_count() as usize;
                 let mut sgi = self.sgi_ptr(cpu);
                 field!(sgi, igroupr0).write(0xffffffff);
+                for (index, bits) in bitmap_registers(0, eppis) {
+                    field!(sgi, igroupr_e).get(index).unwrap().write(bits);
+                }
             }
         }
-        // Put all SPIs into non-secure group 1.
-        let max_reg = self.typer().num_spis() as usize + 32;
-        for i in (32..max_reg).step_by(32) {
-            let bits = if max_reg - i >= 32 {
-                0xffffffff
-            } else {
-                (1 << (max_reg - i)) - 1
-            };
-            field!(self.gicd, igroupr).get(i / 32).unwrap().write(bits);
+        // Put all SPIs and extended SPIs into non-secure group 1.
+        let typer = self.typer();
+        for (index, bits) in bitmap_registers(32, typer.num_spis() as usize + 32) {
+            field!(self.gicd, igroupr).get(index).unwrap().write(bits);
+        }
+        for (index, bits) in bitmap_registers(0, typer.num_espis() as usize) {
+            field!(self.gicd, igroupr_e).get(index).unwrap().write(bits);
         }
 
         // Enable group 1 for the current security state.
@@ -190,53 +441,99 @@ impl GicV3<'_> {
 
     /// Enables or disables the interrupt with the given ID.
     ///
-    /// If it is an SGI or PPI then the CPU core on which to enable it must also be specified;
-    /// otherwise this is ignored and may be `None`.
-    pub fn enable_interrupt(&mut self, intid: IntId, cpu: Option<usize>, enable: bool) {
-        if intid.is_private() {
+    /// If it is an SGI, PPI or extended PPI then the CPU core on which to enable it must also be
+    /// specified; otherwise this is ignored and may be `None`.
+    ///
+    /// If it is an LPI then its entry in the LPI configuration table is updated, and the cached
+    /// configuration invalidated on the given CPU core's redistributor, or on all redistributors if
+    /// `cpu` is `None`. This panics if no LPI configuration table has been set, or if the LPI is
+    /// beyond the range of interrupt IDs which it covers.
+    ///
+    /// Redistributors which don't implement `GICR_INVLPIR` are invalidated through the ITS set
+    /// with [`GicV3::set_its`], which is much more expensive; see there for details. An error is
+    /// returned if no ITS has been set or the ITS fails to carry out the invalidation.
+    pub fn enable_interrupt(
+        &mut self,
+        intid: IntId,
+        cpu: Option<usize>,
+        enable: bool,
+    ) -> Result<(), GICRError> {
+        if intid.is_lpi() {
+            self.modify_lpi_config(intid, cpu, |config| {
+                if enable {
+                    config | Self::LPI_CONFIG_ENABLE
+                } else {
+                    config & !Self::LPI_CONFIG_ENABLE
+                }
+            })?;
+        } else if intid.is_eppi() {
+            let n = self.eppi_index(cpu.unwrap(), intid);
+            let mut sgi = self.sgi_ptr(cpu.unwrap());
+            if enable {
+                set_bit(field!(sgi, isenabler_e).into(), n);
+            } else {
+                set_bit(field!(sgi, icenabler_e).into(), n);
+            }
+        } else if intid.is_private() {
             let mut sgi = self.sgi_ptr(cpu.unwrap());
             if enable {
                 set_bit(field!(sgi, isenabler0).into(), intid.0 as usize);
             } else {
                 set_bit(field!(sgi, icenabler0).into(), intid.0 as usize);
             }
+        } else if intid.is_espi() {
+            if enable {
+                set_bit(field!(self.gicd, isenabler_e).into(), espi_index(intid));
+            } else {
+                set_bit(field!(self.gicd, icenabler_e).into(), espi_index(intid));
+            }
         } else if enable {
             set_bit(field!(self.gicd, isenabler).into(), intid.0 as usize);
         } else {
             set_bit(field!(self.gicd, icenabler).into(), intid.0 as usize);
-        };
+        }
+        Ok(())
     }
 
     /// Enables or disables all interrupts on all CPU cores.
     pub fn enable_all_interrupts(&mut self, enable: bool) {
-        // Calculate the maximum register index for all indices.
-        // The first 32 registers are for SGIs and PPIs.
-        let max_reg = self.typer().num_spis() as usize + 32;
-        for i in (32..max_reg).step_by(32) {
-            let bits = if max_reg - i >= 32 {
-                0xffffffff
+        // The first 32 interrupts are SGIs and PPIs, which are handled by the redistributors.
+        let typer = self.typer();
+        for (index, bits) in bitmap_registers(32, typer.num_spis() as usize + 32) {
+            if enable {
+                field!(self.gicd, isenabler).get(index).unwrap().write(bits);
             } else {
-                (1 << (max_reg - i)) - 1
-            };
+                field!(self.gicd, icenabler).get(index).unwrap().write(bits);
+            }
+        }
+        for (index, bits) in bitmap_registers(0, typer.num_espis() as usize) {
             if enable {
-                field!(self.gicd, isenabler)
-                    .get(i / 32)
+                field!(self.gicd, isenabler_e)
+                    .get(index)
                     .unwrap()
                     .write(bits);
             } else {
-                field!(self.gicd, icenabler)
-                    .get(i / 32)
+                field!(self.gicd, icenabler_e)
+                    .get(index)
                     .unwrap()
                     .write(bits);
             }
         }
         for cpu in 0..self.cpu_count {
+            let eppis = self.gicr_typer(cpu).max_eppi_count() as usize;
             let mut sgi = self.sgi_ptr(cpu);
             if enable {
                 field!(sgi, isenabler0).write(0xffffffff);
             } else {
                 field!(sgi, icenabler0).write(0xffffffff);
             }
+            for (index, bits) in bitmap_registers(0, eppis) {
+                if enable {
+                    field!(sgi, isenabler_e).get(index).unwrap().write(bits);
+                } else {
+                    field!(sgi, icenabler_e).get(index).unwrap().write(bits);
+                }
+            }
         }
     }
 
@@ -252,26 +549,57 @@ impl GicV3<'_> {
     ///
     /// Note that lower numbers correspond to higher priorities; i.e. 0 is the highest priority, and
     /// 255 is the lowest.
-    pub fn set_interrupt_priority(&mut self, intid: IntId, cpu: Option<usize>, priority: u8) {
-        // Affinity routing is enabled, so use the GICR for SGIs and PPIs.
-        if intid.is_private() {
+    ///
+    /// If it is an LPI then the bottom two bits of the priority are ignored, and the cached
+    /// configuration is invalidated as for [`GicV3::enable_interrupt`], which may fail in the same
+    /// way.
+    pub fn set_interrupt_priority(
+        &mut self,
+        intid: IntId,
+        cpu: Option<usize>,
+        priority: u8,
+    ) -> Result<(), GICRError> {
+        if intid.is_lpi() {
+            self.modify_lpi_config(intid, cpu, |config| {
+                (priority & 0xfc) | Self::LPI_CONFIG_RES1 | (config & Self::LPI_CONFIG_ENABLE)
+            })?;
+        } else if intid.is_eppi() {
+            let n = self.eppi_index(cpu.unwrap(), intid);
+            let mut sgi = self.sgi_ptr(cpu.unwrap());
+            field!(sgi, ipriorityr_e).get(n).unwrap().write(priority);
+        } else if intid.is_private() {
+            // Affinity routing is enabled, so use the GICR for SGIs and PPIs.
             let mut sgi = self.sgi_ptr(cpu.unwrap());
             field!(sgi, ipriorityr)
                 .get(intid.0 as usize)
                 .unwrap()
                 .write(priority);
+        } else if intid.is_espi() {
+            field!(self.gicd, ipriorityr_e)
+                .get(espi_index(intid))
+                .unwrap()
+                .write(priority);
         } else {
             field!(self.gicd, ipriorityr)
                 .get(intid.0 as usize)
                 .unwrap()
                 .write(priority);
         }
+        Ok(())
     }
 
     /// Configures the trigger type for the interrupt with the given ID.
     pub fn set_trigger(&mut self, intid: IntId, cpu: Option<usize>, trigger: Trigger) {
-        let index = (intid.0 / 16) as usize;
-        let bit = 1 << (((intid.0 % 16) * 2) + 1);
+        // The GICR_ICFGR<n>E registers for extended PPIs follow those for SGIs and PPIs.
+        let n = if intid.is_espi() {
+            espi_index(intid) as u32
+        } else if intid.is_eppi() {
+            32 + self.eppi_index(cpu.unwrap(), intid) as u32
+        } else {
+            intid.0
+        };
+        let index = (n / 16) as usize;
+        let bit = 1 << (((n % 16) * 2) + 1);
 
         // Affinity routing is enabled, so use the GICR for SGIs and PPIs.
         if intid.is_private() {
@@ -284,7 +612,11 @@ impl GicV3<'_> {
                 Trigger::Level => v & !bit,
             });
         } else {
-            let mut icfgr = field!(self.gicd, icfgr);
+            let mut icfgr = if intid.is_espi() {
+                field!(self.gicd, icfgr_e)
+            } else {
+                field!(self.gicd, icfgr)
+            };
             let mut register = icfgr.get(index).unwrap();
             let v = register.read();
             register.write(match trigger {
@@ -296,7 +628,21 @@ impl GicV3<'_> {
 
     /// Assigns the interrupt with id `intid` to interrupt group `group`.
     pub fn set_group(&mut self, intid: IntId, cpu: Option<usize>, group: Group) {
-        if intid.is_private() {
+        if intid.is_eppi() {
+            let n = self.eppi_index(cpu.unwrap(), intid);
+            let mut sgi = self.sgi_ptr(cpu.unwrap());
+            if let Group::Secure(sg) = group {
+                clear_bit(field!(sgi, igroupr_e).into(), n);
+                let igrpmodr = field!(sgi, igrpmodr_e).into();
+                match sg {
+                    SecureIntGroup::Group1S => set_bit(igrpmodr, n),
+                    SecureIntGroup::Group0 => clear_bit(igrpmodr, n),
+                }
+            } else {
+                set_bit(field!(sgi, igroupr_e).into(), n);
+                clear_bit(field!(sgi, igrpmodr_e).into(), n);
+            }
+        } else if intid.is_private() {
             let mut sgi = self.sgi_ptr(cpu.unwrap());
             if let Group::Secure(sg) = group {
                 clear_bit(field!(sgi, igroupr0).into(), intid.0 as usize);
@@ -309,6 +655,19 @@ impl GicV3<'_> {
                 set_bit(field!(sgi, igroupr0).into(), intid.0 as usize);
                 clear_bit(field!(sgi, igrpmodr0).into(), intid.0 as usize);
             }
+        } else if intid.is_espi() {
+            let n = espi_index(intid);
+            if let Group::Secure(sg) = group {
+                clear_bit(field!(self.gicd, igroupr_e).into(), n);
+                let igrpmodr = field!(self.gicd, igrpmodr_e);
+                match sg {
+                    SecureIntGroup::Group1S => set_bit(igrpmodr.into(), n),
+                    SecureIntGroup::Group0 => clear_bit(igrpmodr.into(), n),
+                }
+            } else {
+                set_bit(field!(self.gicd, igroupr_e).into(), n);
+                clear_bit(field!(self.gicd, igrpmodr_e).into(), n);
+            }
         } else if let Group::Secure(sg) = group {
             let igroupr = field!(self.gicd, igroupr);
             clear_bit(igroupr.into(), intid.0 as usize);
@@ -323,7 +682,423 @@ impl GicV3<'_> {
         };
     }
 
+    /// Configures whether the given interrupt is a non-maskable interrupt (NMI).
+    ///
+    /// If it is an SGI, PPI or extended PPI then the CPU core must also be specified; otherwise
+    /// this is ignored and may be `None`. LPIs can't be NMIs.
+    ///
+    /// Returns an error if the interrupt is an LPI, or if the distributor doesn't support NMIs,
+    /// according to [`Typer::nmi_supported`].
+    pub fn set_nmi(
+        &mut self,
+        intid: IntId,
+        cpu: Option<usize>,
+        nmi: bool,
+    ) -> Result<(), GICDError> {
+        if intid.is_lpi() {
+            return Err(GICDError::NmiNotSupportedForLpi(intid));
+        }
+        if !self.typer().nmi_supported() {
+            return Err(GICDError::NmiNotSupported);
+        }
+
+        if intid.is_eppi() {
+            let n = self.eppi_index(cpu.unwrap(), intid);
+            let mut sgi = self.sgi_ptr(cpu.unwrap());
+            modify_bit(field!(sgi, inmir_e).into(), n, nmi);
+        } else if intid.is_private() {
+            let mut sgi = self.sgi_ptr(cpu.unwrap());
+            modify_bit(field!(sgi, inmir0).into(), intid.0 as usize, nmi);
+        } else if intid.is_espi() {
+            modify_bit(field!(self.gicd, inmr_e).into(), espi_index(intid), nmi);
+        } else {
+            modify_bit(field!(self.gicd, inmir).into(), intid.0 as usize, nmi);
+        }
+        Ok(())
+    }
+
+    /// Returns whether the given interrupt is enabled.
+    ///
+    /// If it is an SGI, PPI or extended PPI then the CPU core must also be specified; otherwise
+    /// this is ignored and may be `None`. If it is an LPI then it is read from the LPI
+    /// configuration table, and this panics if no table has been set or if the LPI is beyond the
+    /// range it covers.
+    pub fn is_enabled(&mut self, intid: IntId, cpu: Option<usize>) -> bool {
+        if intid.is_lpi() {
+            self.lpi_config(intid) & Self::LPI_CONFIG_ENABLE != 0
+        } else if intid.is_eppi() {
+            let n = self.eppi_index(cpu.unwrap(), intid);
+            let sgi = self.sgi_ptr(cpu.unwrap());
+            get_bit(field_shared!(sgi, isenabler_e).into(), n)
+        } else if intid.is_private() {
+            let sgi = self.sgi_ptr(cpu.unwrap());
+            get_bit(field_shared!(sgi, isenabler0).into(), intid.0 as usize)
+        } else if intid.is_espi() {
+            get_bit(
+                field_shared!(self.gicd, isenabler_e).into(),
+                espi_index(intid),
+            )
+        } else {
+            get_bit(field_shared!(self.gicd, isenabler).into(), intid.0 as usize)
+        }
+    }
+
+    /// Returns the priority of the given interrupt.
+    ///
+    /// The CPU core must be specified as for [`GicV3::is_enabled`]. Bits of the priority which are
+    /// not implemented read as zero.
+    pub fn priority(&mut self, intid: IntId, cpu: Option<usize>) -> u8 {
+        if intid.is_lpi() {
+            self.lpi_config(intid) & 0xfc
+        } else if intid.is_eppi() {
+            let n = self.eppi_index(cpu.unwrap(), intid);
+            let sgi = self.sgi_ptr(cpu.unwrap());
+            field_shared!(sgi, ipriorityr_e).get(n).unwrap().read()
+        } else if intid.is_private() {
+            let sgi = self.sgi_ptr(cpu.unwrap());
+            field_shared!(sgi, ipriorityr)
+                .get(intid.0 as usize)
+                .unwrap()
+                .read()
+        } else if intid.is_espi() {
+            field_shared!(self.gicd, ipriorityr_e)
+                .get(espi_index(intid))
+                .unwrap()
+                .read()
+        } else {
+            field_shared!(self.gicd, ipriorityr)
+                .get(intid.0 as usize)
+                .unwrap()
+                .read()
+        }
+    }
+
+    /// Returns the trigger type of the given interrupt.
+    ///
+    /// The CPU core must be specified as for [`GicV3::is_enabled`]. LPIs are always
+    /// edge-triggered.
+    pub fn trigger(&mut self, intid: IntId, cpu: Option<usize>) -> Trigger {
+        if intid.is_lpi() {
+            return Trigger::Edge;
+        }
+
+        // The GICR_ICFGR<n>E registers for extended PPIs follow those for SGIs and PPIs.
+        let n = if intid.is_espi() {
+            espi_index(intid) as u32
+        } else if intid.is_eppi() {
+            32 + self.eppi_index(cpu.unwrap(), intid) as u32
+        } else {
+            intid.0
+        };
+        let index = (n / 16) as usize;
+        let bit = 1 << (((n % 16) * 2) + 1);
+
+        let icfgr = if intid.is_private() {
+            let sgi = self.sgi_ptr(cpu.unwrap());
+            field_shared!(sgi, icfgr).get(index).unwrap().read()
+        } else if intid.is_espi() {
+            field_shared!(self.gicd, icfgr_e).get(index).unwrap().read()
+        } else {
+            field_shared!(self.gicd, icfgr).get(index).unwrap().read()
+        };
+        if icfgr & bit != 0 {
+            Trigger::Edge
+        } else {
+            Trigger::Level
+        }
+    }
+
+    /// Returns the group of the given interrupt.
+    ///
+    /// The CPU core must be specified as for [`GicV3::is_enabled`]. LPIs are always in non-secure
+    /// group 1.
+    pub fn group(&mut self, intid: IntId, cpu: Option<usize>) -> Group {
+        if intid.is_lpi() {
+            return Group::Group1NS;
+        }
+
+        let (group1, modifier) = if intid.is_eppi() {
+            let n = self.eppi_index(cpu.unwrap(), intid);
+            let sgi = self.sgi_ptr(cpu.unwrap());
+            (
+                get_bit(field_shared!(sgi, igroupr_e).into(), n),
+                get_bit(field_shared!(sgi, igrpmodr_e).into(), n),
+            )
+        } else if intid.is_private() {
+            let sgi = self.sgi_ptr(cpu.unwrap());
+            (
+                get_bit(field_shared!(sgi, igroupr0).into(), intid.0 as usize),
+                get_bit(field_shared!(sgi, igrpmodr0).into(), intid.0 as usize),
+            )
+        } else if intid.is_espi() {
+            (
+                get_bit(
+                    field_shared!(self.gicd, igroupr_e).into(),
+                    espi_index(intid),
+                ),
+                get_bit(
+                    field_shared!(self.gicd, igrpmodr_e).into(),
+                    espi_index(intid),
+                ),
+            )
+        } else {
+            (
+                get_bit(field_shared!(self.gicd, igroupr).into(), intid.0 as usize),
+                get_bit(field_shared!(self.gicd, igrpmodr).into(), intid.0 as usize),
+            )
+        };
+        match (group1, modifier) {
+            (true, _) => Group::Group1NS,
+            (false, true) => Group::Secure(SecureIntGroup::Group1S),
+            (false, false) => Group::Secure(SecureIntGroup::Group0),
+        }
+    }
+
+    /// Returns a snapshot of the configuration of the given interrupt.
+    ///
+    /// The CPU core must be specified as for [`GicV3::is_enabled`].
+    pub fn interrupt_config(&mut self, intid: IntId, cpu: Option<usize>) -> InterruptConfig {
+        InterruptConfig {
+            enabled: self.is_enabled(intid, cpu),
+            priority: self.priority(intid, cpu),
+            trigger: self.trigger(intid, cpu),
+            group: self.group(intid, cpu),
+            route: self.get_spi_route(intid).ok(),
+        }
+    }
+
+    /// Sets which CPU core or cores the given SPI or extended SPI is routed to.
+    ///
+    /// Returns an error if the interrupt is not an SPI or extended SPI, or if 1 of N routing is
+    /// requested but not supported by the distributor.
+    pub fn set_spi_route(&mut self, intid: IntId, route: SpiRoute) -> Result<(), GICDError> {
+        if route == SpiRoute::OneOfN && !self.typer().one_of_n_supported() {
+            return Err(GICDError::OneOfNNotSupported);
+        }
+        let mut irouter = self.irouter_ptr(intid)?;
+        irouter.write(route.to_irouter());
+        Ok(())
+    }
+
+    /// Returns which CPU core or cores the given SPI or extended SPI is routed to.
+    ///
+    /// Returns an error if the interrupt is not an SPI or extended SPI.
+    pub fn get_spi_route(&self, intid: IntId) -> Result<SpiRoute, GICDError> {
+        let irouter = if intid.is_spi() {
+            field_shared!(self.gicd, irouter)
+                .get((intid.0 - IntId::SPI_START) as usize)
+                .unwrap()
+                .read()
+        } else if intid.is_espi() {
+            field_shared!(self.gicd, irouter_e)
+                .get(espi_index(intid))
+                .unwrap()
+                .read()
+        } else {
+            return Err(GICDError::NotAnSpi(intid));
+        };
+        Ok(SpiRoute::from_irouter(irouter))
+    }
+
+    /// Returns a pointer to the `GICD_IROUTER<n>` or `GICD_IROUTER<n>E` register for the given
+    /// interrupt.
+    fn irouter_ptr(
+        &mut self,
+        intid: IntId,
+    ) -> Result<UniqueMmioPointer<'_, ReadPureWrite<u64>>, GICDError> {
+        if intid.is_spi() {
+            Ok(field!(self.gicd, irouter)
+                .take((intid.0 - IntId::SPI_START) as usize)
+                .unwrap())
+        } else if intid.is_espi() {
+            Ok(field!(self.gicd, irouter_e)
+                .take(espi_index(intid))
+                .unwrap())
+        } else {
+            Err(GICDError::NotAnSpi(intid))
+        }
+    }
+
+    /// Sets the given interrupt to be pending.
+    ///
+    /// If it is an SGI, PPI or extended PPI then the CPU core on which to set it pending must also
+    /// be specified; otherwise this is ignored and may be `None`. LPIs are not supported; see
+    /// [`GicV3::set_lpi_pending`] instead.
+    pub fn set_pending(&mut self, intid: IntId, cpu: Option<usize>) {
+        self.write_state_bit(intid, cpu, StateRegister::SetPending);
+    }
+
+    /// Clears the pending state of the given interrupt.
+    ///
+    /// The CPU core must be specified as for [`GicV3::set_pending`].
+    pub fn clear_pending(&mut self, intid: IntId, cpu: Option<usize>) {
+        self.write_state_bit(intid, cpu, StateRegister::ClearPending);
+    }
+
+    /// Returns whether the given interrupt is pending.
+    ///
+    /// The CPU core must be specified as for [`GicV3::set_pending`].
+    pub fn is_pending(&mut self, intid: IntId, cpu: Option<usize>) -> bool {
+        self.read_state_bit(intid, cpu, StateRegister::SetPending)
+    }
+
+    /// Sets the given interrupt to be active.
+    ///
+    /// The CPU core must be specified as for [`GicV3::set_pending`].
+    pub fn set_active(&mut self, intid: IntId, cpu: Option<usize>) {
+        self.write_state_bit(intid, cpu, StateRegister::SetActive);
+    }
+
+    /// Clears the active state of the given interrupt.
+    ///
+    /// This may be used to recover an interrupt which was acknowledged but never ended. The CPU
+    /// core must be specified as for [`GicV3::set_pending`].
+    pub fn clear_active(&mut self, intid: IntId, cpu: Option<usize>) {
+        self.write_state_bit(intid, cpu, StateRegister::ClearActive);
+    }
+
+    /// Returns whether the given interrupt is active.
+    ///
+    /// The CPU core must be specified as for [`GicV3::set_pending`].
+    pub fn is_active(&mut self, intid: IntId, cpu: Option<usize>) -> bool {
+        self.read_state_bit(intid, cpu, StateRegister::SetActive)
+    }
+
+    /// Returns a pointer to the given pending or active state registers for the given interrupt,
+    /// along with the index of the interrupt's bit within them.
+    fn state_registers(
+        &mut self,
+        intid: IntId,
+        cpu: Option<usize>,
+        register: StateRegister,
+    ) -> (UniqueMmioPointer<'_, [ReadPureWrite<u32>]>, usize) {
+        assert!(!intid.is_lpi());
+
+        if intid.is_eppi() {
+            let n = self.eppi_index(cpu.unwrap(), intid);
+            let sgi = self.sgi_ptr(cpu.unwrap());
+            // SAFETY: We only split out a single field.
+            let registers = unsafe {
+                match register {
+                    StateRegister::SetPending => split_fields!(sgi, ispendr_e),
+                    StateRegister::ClearPending => split_fields!(sgi, icpendr_e),
+                    StateRegister::SetActive => split_fields!(sgi, isactive_e),
+                    StateRegister::ClearActive => split_fields!(sgi, icactive_e),
+                }
+            };
+            (registers.into(), n)
+        } else if intid.is_private() {
+            // Affinity routing is enabled, so use the GICR for SGIs and PPIs.
+            let sgi = self.sgi_ptr(cpu.unwrap());
+            // SAFETY: We only split out a single field.
+            let register = unsafe {
+                match register {
+                    StateRegister::SetPending => split_fields!(sgi, ispendr0),
+                    StateRegister::ClearPending => split_fields!(sgi, icpendr0),
+                    StateRegister::SetActive => split_fields!(sgi, isactiver0),
+                    StateRegister::ClearActive => split_fields!(sgi, icactiver0),
+                }
+            };
+            (register.into(), intid.0 as usize)
+        } else if intid.is_espi() {
+            let registers = match register {
+                StateRegister::SetPending => field!(self.gicd, ispendr_e),
+                StateRegister::ClearPending => field!(self.gicd, icpendr_e),
+                StateRegister::SetActive => field!(self.gicd, isactive_e),
+                StateRegister::ClearActive => field!(self.gicd, icactive_e),
+            };
+            (registers.into(), espi_index(intid))
+        } else {
+            let registers = match register {
+                StateRegister::SetPending => field!(self.gicd, ispendr),
+                StateRegister::ClearPending => field!(self.gicd, icpendr),
+                StateRegister::SetActive => field!(self.gicd, isactiver),
+                StateRegister::ClearActive => field!(self.gicd, icactiver),
+            };
+            (registers.into(), intid.0 as usize)
+        }
+    }
+
+    /// Writes the bit for the given interrupt to the given set or clear register, leaving the state
+    /// of other interrupts unchanged.
+    fn write_state_bit(&mut self, intid: IntId, cpu: Option<usize>, register: StateRegister) {
+        let (mut registers, n) = self.state_registers(intid, cpu, register);
+        registers.get(n / 32).unwrap().write(1 << (n % 32));
+    }
+
+    /// Reads the bit for the given interrupt from the given set or clear register.
+    fn read_state_bit(
+        &mut self,
+        intid: IntId,
+        cpu: Option<usize>,
+        register: StateRegister,
+    ) -> bool {
+        let (mut registers, n) = self.state_registers(intid, cpu, register);
+        registers.get(n / 32).unwrap().read() & (1 << (n % 32)) != 0
+    }
+
+    /// Sets the given SPI or extended SPI by writing its ID to `GICD_SETSPI_NSR`, as a device
+    /// would with a message-based interrupt.
+    ///
+    /// This makes an edge-triggered interrupt pending, or asserts a level-triggered interrupt
+    /// until [`GicV3::clear_message_spi`] is called.
+    ///
+    /// Returns an error if the interrupt is not an SPI or extended SPI, or if the distributor
+    /// doesn't support message-based SPIs.
+    pub fn set_message_spi(&mut self, intid: IntId) -> Result<(), GICDError> {
+        self.check_message_spi(intid)?;
+        field!(self.gicd, setspi_nsr).write(intid.0);
+        Ok(())
+    }
+
+    /// Clears the given SPI or extended SPI by writing its ID to `GICD_CLRSPI_NSR`.
+    ///
+    /// This deasserts a level-triggered interrupt which was asserted by
+    /// [`GicV3::set_message_spi`] or by a device writing to `GICD_SETSPI_NSR`.
+    ///
+    /// Returns an error if the interrupt is not an SPI or extended SPI, or if the distributor
+    /// doesn't support message-based SPIs.
+    pub fn clear_message_spi(&mut self, intid: IntId) -> Result<(), GICDError> {
+        self.check_message_spi(intid)?;
+        field!(self.gicd, clrspi_nsr).write(intid.0);
+        Ok(())
+    }
+
+    /// Returns the message which a device such as a PCIe endpoint should write to set the given
+    /// SPI or extended SPI, without going through an ITS.
+    ///
+    /// `gicd_physical_address` must be the physical address of the distributor, as seen by the
+    /// device. The interrupt should usually be configured as edge-triggered.
+    ///
+    /// Returns an error if the interrupt is not an SPI or extended SPI, or if the distributor
+    /// doesn't support message-based SPIs.
+    pub fn spi_msi_message(
+        &self,
+        intid: IntId,
+        gicd_physical_address: u64,
+    ) -> Result<MsiMessage, GICDError> {
+        self.check_message_spi(intid)?;
+        Ok(MsiMessage {
+            address: gicd_physical_address + offset_of!(Gicd, setspi_nsr) as u64,
+            data: intid.0,
+        })
+    }
+
+    fn check_message_spi(&self, intid: IntId) -> Result<(), GICDError> {
+        if !(intid.is_spi() || intid.is_espi()) {
+            Err(GICDError::NotAnSpi(intid))
+        } else if !self.typer().mpis_supported() {
+            Err(GICDError::MessageBasedSpisNotSupported)
+        } else {
+            Ok(())
+        }
+    }
+
     /// Sends a group `group` software-generated interrupt (SGI) to the given cores.
+    ///
+    /// A non-zero `affinity3` may only be used if the CPU interface supports Aff3, as reported by
+    /// [`IccCtlr::A3V`](registers::IccCtlr::A3V) in [`GicV3::cpu_interface_control`]. Callers
+    /// which need it should check this once rather than before every SGI.
     #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
     pub fn send_sgi(intid: IntId, target: SgiTarget, group: SgiTargetGroup) {
         assert!(intid.is_sgi());
@@ -376,16 +1151,37 @@ impl GicV3<'_> {
 
     /// Gets the ID of the highest priority signalled group `group` interrupt, and acknowledges it.
     ///
-    /// Returns `None` if there is no pending interrupt of sufficient priority.
+    /// Returns [`AcknowledgedInterrupt::None`] if there is no pending interrupt of sufficient
+    /// priority.
+    ///
+    /// If non-maskable interrupts are enabled and the highest priority group 1 interrupt is an NMI
+    /// then this doesn't acknowledge it, but returns [`AcknowledgedInterrupt::Nmi`]. The NMI should
+    /// then be acknowledged with [`GicV3::get_and_acknowledge_nmi`].
     #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
-    pub fn get_and_acknowledge_interrupt(group: InterruptGroup) -> Option<IntId> {
+    pub fn get_and_acknowledge_interrupt(group: InterruptGroup) -> AcknowledgedInterrupt {
         let icc_iar = match group {
             InterruptGroup::Group0 => read_icc_iar0_el1(),
             InterruptGroup::Group1 => read_icc_iar1_el1(),
         };
 
-        let intid = IntId(icc_iar);
-        if intid == IntId::SPECIAL_NONE {
+        match IntId(icc_iar) {
+            IntId::SPECIAL_NONE => AcknowledgedInterrupt::None,
+            IntId::SPECIAL_NMI => AcknowledgedInterrupt::Nmi,
+            intid => AcknowledgedInterrupt::Interrupt(intid),
+        }
+    }
+
+    /// Gets the ID of the highest priority signalled group 1 non-maskable interrupt, and
+    /// acknowledges it.
+    ///
+    /// Returns `None` if the highest priority pending group 1 interrupt is not an NMI. NMIs are
+    /// ended like other group 1 interrupts, with [`GicV3::end_interrupt`].
+    ///
+    /// This is only available on AArch64, as `ICC_NMIAR1_EL1` has no AArch32 equivalent.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64"))]
+    pub fn get_and_acknowledge_nmi() -> Option<IntId> {
+        let intid = IntId(read_icc_nmiar1_el1());
+        if intid == IntId::SPECIAL_NONE || intid == IntId::SPECIAL_NMI {
             None
         } else {
             Some(intid)
@@ -394,6 +1190,10 @@ impl GicV3<'_> {
 
     /// Informs the interrupt controller that the CPU has completed processing the given group `group` interrupt.
     /// This drops the interrupt priority and deactivates the interrupt.
+    ///
+    /// If split EOI has been enabled with [`GicV3::enable_split_eoi`] then this only drops the
+    /// priority, and the interrupt must later be deactivated with
+    /// [`GicV3::deactivate_interrupt`].
     #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
     pub fn end_interrupt(intid: IntId, group: InterruptGroup) {
         match group {
@@ -402,41 +1202,228 @@ impl GicV3<'_> {
         }
     }
 
-    /// Returns information about what the GIC implementation supports.
-    pub fn typer(&self) -> Typer {
-        field_shared!(self.gicd, typer).read()
-    }
-
-    /// Returns information about selected GIC redistributor.
-    pub fn gicr_typer(&mut self, cpu: usize) -> GicrTyper {
-        field_shared!(self.gicr_ptr(cpu), typer).read()
+    /// Deactivates the given interrupt, after its priority has been dropped by
+    /// [`GicV3::end_interrupt`].
+    ///
+    /// This should only be used if split EOI has been enabled with [`GicV3::enable_split_eoi`].
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn deactivate_interrupt(intid: IntId) {
+        write_icc_dir_el1(intid.0);
     }
 
-    /// Returns a pointer to the GIC distributor registers.
+    /// Enables or disables split EOI for the current CPU core, by setting `ICC_CTLR_EL1.EOImode`.
     ///
-    /// This may be used to read and write the registers directly for functionality not yet
-    /// supported by this driver.
-    pub fn gicd_ptr(&mut self) -> UniqueMmioPointer<Gicd> {
-        self.gicd.reborrow()
+    /// When enabled, [`GicV3::end_interrupt`] only drops the priority of the interrupt, and
+    /// [`GicV3::deactivate_interrupt`] must be called separately to deactivate it. This should be
+    /// called after [`GicV3::init_cpu`], which disables it.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn enable_split_eoi(enable: bool) {
+        Self::modify_cpu_interface_control(IccCtlr::EOImode, enable);
     }
 
-    /// Returns a pointer to the GIC redistributor, SGI and PPI registers.
-    fn gicr_sgi_ptr(&mut self, cpu: usize) -> UniqueMmioPointer<GicrSgi> {
-        assert!(cpu < self.cpu_count);
-        // SAFETY: The caller of `GicV3::new` promised that `gicr_base` and `gicr_stride` were valid
-        // and there are no aliases.
-        unsafe {
-            UniqueMmioPointer::new(
-                NonNull::new(self.gicr_base.wrapping_byte_add(cpu * self.gicr_stride)).unwrap(),
-            )
-        }
+    /// Enables or disables the use of `ICC_BPR0_EL1` for both group 0 and group 1 interrupts on
+    /// the current CPU core, by setting `ICC_CTLR_EL1.CBPR`.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn enable_common_binary_point(enable: bool) {
+        Self::modify_cpu_interface_control(IccCtlr::CBPR, enable);
+    }
+
+    /// Enables or disables the use of the current CPU core's priority mask as a hint for interrupt
+    /// distribution, by setting `ICC_CTLR_EL1.PMHE`.
+    ///
+    /// This has no effect if the distributor doesn't support it.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn enable_priority_mask_hint(enable: bool) {
+        Self::modify_cpu_interface_control(IccCtlr::PMHE, enable);
+    }
+
+    /// Returns the value of `ICC_CTLR_EL1` for the current CPU core, which describes what its CPU
+    /// interface supports as well as how it is configured.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn cpu_interface_control() -> IccCtlr {
+        IccCtlr::from_bits_retain(read_icc_ctlr_el1())
+    }
+
+    /// Writes `ICC_CTLR_EL1` for the current CPU core.
+    ///
+    /// Only the `PMHE`, `EOImode` and `CBPR` fields are writable.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn set_cpu_interface_control(ctlr: IccCtlr) {
+        write_icc_ctlr_el1(ctlr.bits());
+    }
+
+    /// Sets or clears the given flag of `ICC_CTLR_EL1` for the current CPU core, leaving the other
+    /// fields unchanged.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    fn modify_cpu_interface_control(flag: IccCtlr, enable: bool) {
+        let mut ctlr = Self::cpu_interface_control();
+        ctlr.set(flag, enable);
+        Self::set_cpu_interface_control(ctlr);
+    }
+
+    /// Returns the binary point for the given interrupt group on the current CPU core, from
+    /// `ICC_BPR0_EL1` or `ICC_BPR1_EL1`.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn binary_point(group: InterruptGroup) -> u8 {
+        let bpr = match group {
+            InterruptGroup::Group0 => read_icc_bpr0_el1(),
+            InterruptGroup::Group1 => read_icc_bpr1_el1(),
+        };
+        (bpr & 0b111) as u8
+    }
+
+    /// Sets the binary point for the given interrupt group on the current CPU core.
+    ///
+    /// This splits interrupt priorities into a group priority field, which determines whether one
+    /// interrupt can preempt another, and a subpriority field. For group 0 the group priority field
+    /// is bits `[7:binary_point + 1]`, while for group 1 it is bits `[7:binary_point]`. Values
+    /// below the minimum supported by the implementation are increased to the minimum.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn set_binary_point(group: InterruptGroup, binary_point: u8) {
+        assert!(binary_point < 8);
+        match group {
+            InterruptGroup::Group0 => write_icc_bpr0_el1(binary_point.into()),
+            InterruptGroup::Group1 => write_icc_bpr1_el1(binary_point.into()),
+        }
+    }
+
+    /// Returns the number of priority bits implemented by the current CPU interface, from
+    /// `ICC_CTLR_EL1.PRIbits`.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn priority_bits() -> u32 {
+        Self::cpu_interface_control().priority_bits()
+    }
+
+    /// Returns the number of preemption levels available to interrupts of the given group on the
+    /// current CPU core, given its current binary point and the implemented priority bits.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn preemption_levels(group: InterruptGroup) -> u32 {
+        let binary_point = Self::binary_point(group).into();
+        let group_priority_lsb = match group {
+            InterruptGroup::Group0 => binary_point + 1,
+            InterruptGroup::Group1 => binary_point,
+        };
+        preemption_levels(Self::priority_bits(), group_priority_lsb)
+    }
+
+    /// Returns the running priority of the current CPU core, from `ICC_RPR_EL1`.
+    ///
+    /// This is the group priority of the highest priority active interrupt, or 0xff if there is no
+    /// active interrupt.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn running_priority() -> u8 {
+        read_icc_rpr_el1() as u8
+    }
+
+    /// Saves the active priorities of the current CPU core, from the `ICC_AP0R<n>_EL1` and
+    /// `ICC_AP1R<n>_EL1` registers implemented for its number of priority bits.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn active_priorities() -> ActivePriorities {
+        let mut active_priorities = ActivePriorities {
+            count: ActivePriorities::register_count(Self::priority_bits()),
+            group0: [0; 4],
+            group1: [0; 4],
+        };
+        for n in 0..active_priorities.count {
+            active_priorities.group0[n] = match n {
+                0 => read_icc_ap0r0_el1(),
+                1 => read_icc_ap0r1_el1(),
+                2 => read_icc_ap0r2_el1(),
+                _ => read_icc_ap0r3_el1(),
+            };
+            active_priorities.group1[n] = match n {
+                0 => read_icc_ap1r0_el1(),
+                1 => read_icc_ap1r1_el1(),
+                2 => read_icc_ap1r2_el1(),
+                _ => read_icc_ap1r3_el1(),
+            };
+        }
+        active_priorities
+    }
+
+    /// Restores active priorities previously saved by [`GicV3::active_priorities`] to the current
+    /// CPU core.
+    ///
+    /// This should only be done when the CPU interface has no active interrupts, such as when
+    /// resuming an interrupted context on a different core.
+    #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
+    pub fn restore_active_priorities(active_priorities: &ActivePriorities) {
+        for n in 0..active_priorities.count {
+            let group0 = active_priorities.group0[n];
+            let group1 = active_priorities.group1[n];
+            match n {
+                0 => {
+                    write_icc_ap0r0_el1(group0);
+                    write_icc_ap1r0_el1(group1);
+                }
+                1 => {
+                    write_icc_ap0r1_el1(group0);
+                    write_icc_ap1r1_el1(group1);
+                }
+                2 => {
+                    write_icc_ap0r2_el1(group0);
+                    write_icc_ap1r2_el1(group1);
+                }
+                _ => {
+                    write_icc_ap0r3_el1(group0);
+                    write_icc_ap1r3_el1(group1);
+                }
+            }
+        }
+    }
+
+    /// Returns information about what the GIC implementation supports.
+    pub fn typer(&self) -> Typer {
+        field_shared!(self.gicd, typer).read()
+    }
+
+    /// Returns information about the GIC implementation from `GICD_TYPER2`.
+    pub fn typer2(&self) -> Typer2 {
+        field_shared!(self.gicd, typer2).read()
+    }
+
+    /// Returns information about selected GIC redistributor.
+    pub fn gicr_typer(&mut self, cpu: usize) -> GicrTyper {
+        field_shared!(self.gicr_ptr(cpu), typer).read()
+    }
+
+    /// Returns the offset of the given extended PPI from the start of the extended PPI range.
+    ///
+    /// Panics if the given CPU core's redistributor doesn't implement the extended PPI.
+    fn eppi_index(&mut self, cpu: usize, intid: IntId) -> usize {
+        let index = intid.0 - IntId::EPPI_START;
+        assert!(
+            index < self.gicr_typer(cpu).max_eppi_count(),
+            "{intid:?} is not implemented by the redistributor for CPU {cpu}"
+        );
+        index as usize
+    }
+
+    /// Returns a pointer to the GIC distributor registers.
+    ///
+    /// This may be used to read and write the registers directly for functionality not yet
+    /// supported by this driver.
+    pub fn gicd_ptr(&mut self) -> UniqueMmioPointer<'_, Gicd> {
+        self.gicd.reborrow()
+    }
+
+    /// Returns a pointer to the GIC redistributor, SGI and PPI registers.
+    fn gicr_sgi_ptr(&mut self, cpu: usize) -> UniqueMmioPointer<'_, GicrSgi> {
+        assert!(cpu < self.cpu_count);
+        // SAFETY: The caller of `GicV3::new` promised that `gicr_base` and `gicr_stride` were valid
+        // and there are no aliases.
+        unsafe {
+            UniqueMmioPointer::new(
+                NonNull::new(self.gicr_base.wrapping_byte_add(cpu * self.gicr_stride)).unwrap(),
+            )
+        }
     }
 
     /// Returns a pointer to the GIC redistributor registers.
     ///
     /// This may be used to read and write the registers directly for functionality not yet
     /// supported by this driver.
-    pub fn gicr_ptr(&mut self, cpu: usize) -> UniqueMmioPointer<Gicr> {
+    pub fn gicr_ptr(&mut self, cpu: usize) -> UniqueMmioPointer<'_, Gicr> {
         // SAFETY: We only split out a single field.
         unsafe { split_fields!(self.gicr_sgi_ptr(cpu), gicr) }
     }
@@ -445,11 +1432,38 @@ impl GicV3<'_> {
     ///
     /// This may be used to read and write the registers directly for functionality not yet
     /// supported by this driver.
-    pub fn sgi_ptr(&mut self, cpu: usize) -> UniqueMmioPointer<Sgi> {
+    pub fn sgi_ptr(&mut self, cpu: usize) -> UniqueMmioPointer<'_, Sgi> {
         // SAFETY: We only split out a single field.
         unsafe { split_fields!(self.gicr_sgi_ptr(cpu), sgi) }
     }
 
+    /// Returns a pointer to the GICv4 redistributor virtual LPI registers.
+    ///
+    /// This may be used to read and write the registers directly for functionality not yet
+    /// supported by this driver.
+    ///
+    /// Panics if the redistributors don't have a virtual LPI frame.
+    pub fn vlpi_ptr(&mut self, cpu: usize) -> UniqueMmioPointer<'_, GicrVlpi> {
+        assert!(cpu < self.cpu_count);
+        assert_eq!(
+            self.gicr_stride,
+            size_of::<GicrSgi>() * 2,
+            "Redistributors don't have virtual LPI frames"
+        );
+        // SAFETY: The caller of `GicV3::new` promised that `gicr_base` and `gicr_stride` were valid
+        // and there are no aliases, and the stride shows that the VLPI frame is present.
+        unsafe {
+            UniqueMmioPointer::new(
+                NonNull::new(
+                    self.gicr_base
+                        .wrapping_byte_add(cpu * self.gicr_stride + size_of::<GicrSgi>())
+                        .cast(),
+                )
+                .unwrap(),
+            )
+        }
+    }
+
     /// Blocks until register write for the current Security state is no longer in progress.
     pub fn gicd_barrier(&self) {
         while field_shared!(self.gicd, ctlr)
@@ -466,6 +1480,27 @@ impl GicV3<'_> {
         self.gicd_barrier();
     }
 
+    /// Configures whether SGIs have an active state, by setting or clearing `GICD_CTLR.nASSGIreq`.
+    ///
+    /// Without an active state, GICv4.1 vSGIs can be delivered directly to resident vPEs. This
+    /// should be called while the distributor is disabled, before [`GicV3::setup`].
+    ///
+    /// Returns an error if the distributor doesn't support it, according to
+    /// [`Typer2::nassgi_capable`].
+    pub fn enable_nassgi(&mut self, enable: bool) -> Result<(), GICDError> {
+        if !self.typer2().nassgi_capable() {
+            return Err(GICDError::NassgiNotSupported);
+        }
+        self.gicd_modify_control(|old| {
+            if enable {
+                old | GicdCtlr::nASSGIreq
+            } else {
+                old - GicdCtlr::nASSGIreq
+            }
+        });
+        Ok(())
+    }
+
     /// Clears specified bits in GIC distributor control register.
     pub fn gicd_clear_control(&mut self, flags: GicdCtlr) {
         self.gicd_modify_control(|old| old - flags);
@@ -557,6 +1592,512 @@ impl GicV3<'_> {
         }
     }
 
+    /// Sets the memory to use for the LPI configuration table, which is shared by all
+    /// redistributors.
+    ///
+    /// The table has one byte for each LPI, and must be 4 KiB aligned. The number of interrupt ID
+    /// bits programmed into the redistributors is the largest supported by the GIC for which the
+    /// table is big enough, so it must be at least 8 KiB. All LPIs are initially disabled.
+    ///
+    /// Returns an error if LPIs are already enabled on any redistributor, as the table can't be
+    /// changed after that.
+    ///
+    /// # Safety
+    ///
+    /// `physical_address` must be the physical address of `config_table`, and `config_table` must
+    /// be mapped with the given memory attributes.
+    pub unsafe fn set_lpi_config_table(
+        &mut self,
+        config_table: &'a mut [u8],
+        physical_address: u64,
+        attributes: MemoryAttributes,
+    ) -> Result<(), GICRError> {
+        if !self.typer().lpis_supported() {
+            return Err(GICRError::LpisNotSupported);
+        }
+        for cpu in 0..self.cpu_count {
+            if self.lpis_enabled(cpu) {
+                return Err(GICRError::LpisAlreadyEnabled);
+            }
+        }
+
+        // The table doesn't include entries for the first 8192 interrupt IDs, which aren't LPIs.
+        // The distributor may support fewer interrupt ID bits than are needed to cover all LPIs.
+        let typer = self.typer();
+        let max_id_bits = (8192 + u64::from(typer.num_lpis()))
+            .next_power_of_two()
+            .trailing_zeros()
+            .min(typer.id_bits());
+        let id_bits = (14..=max_id_bits)
+            .rev()
+            .find(|&id_bits| (1 << id_bits) - 8192 <= config_table.len() as u64)
+            .ok_or(GICRError::InvalidLpiTable)?;
+        if !physical_address.is_multiple_of(0x1000) {
+            return Err(GICRError::InvalidLpiTable);
+        }
+
+        let table = &mut config_table[..((1u64 << id_bits) - 8192) as usize];
+        table.fill(Self::LPI_DEFAULT_PRIORITY | Self::LPI_CONFIG_RES1);
+        dsb_st();
+
+        self.lpi_config_table = Some(LpiConfigTable {
+            table,
+            physical_address,
+            attributes,
+            id_bits,
+        });
+        Ok(())
+    }
+
+    /// Returns whether LPIs are enabled on the given CPU core's redistributor.
+    pub fn lpis_enabled(&mut self, cpu: usize) -> bool {
+        field_shared!(self.gicr_ptr(cpu), ctlr)
+            .read()
+            .contains(GicrCtlr::EnableLPIs)
+    }
+
+    /// Enables LPIs on the given CPU core's redistributor, using the given memory for its LPI
+    /// pending table and the configuration table previously passed to
+    /// [`GicV3::set_lpi_config_table`].
+    ///
+    /// The pending table must be 64 KiB aligned, and have one bit for each interrupt ID covered by
+    /// the configuration table, including the first 8192 which aren't LPIs. It is zeroed before use
+    /// and accessed with the same memory attributes as the configuration table.
+    ///
+    /// Returns an error if LPIs are already enabled on the redistributor.
+    ///
+    /// # Safety
+    ///
+    /// `physical_address` must be the physical address of `pending_table`, and `pending_table`
+    /// must be mapped with the same memory attributes as the configuration table.
+    pub unsafe fn enable_lpis(
+        &mut self,
+        cpu: usize,
+        pending_table: &'a mut [u8],
+        physical_address: u64,
+    ) -> Result<(), GICRError> {
+        if !self.gicr_typer(cpu).physical_lpis_supported() {
+            return Err(GICRError::LpisNotSupported);
+        }
+        if self.lpis_enabled(cpu) {
+            return Err(GICRError::LpisAlreadyEnabled);
+        }
+        let config_table = self
+            .lpi_config_table
+            .as_ref()
+            .ok_or(GICRError::NoLpiConfigTable)?;
+        if (pending_table.len() as u64) < (1 << config_table.id_bits) / 8
+            || !physical_address.is_multiple_of(0x10000)
+        {
+            return Err(GICRError::InvalidLpiTable);
+        }
+
+        let attributes = config_table.attributes;
+        let propbaser = attributes.to_gicr_bits()
+            | (config_table.physical_address & 0x000f_ffff_ffff_f000)
+            | u64::from(config_table.id_bits - 1);
+        let pendbaser = attributes.to_gicr_bits()
+            | (physical_address & 0x000f_ffff_ffff_0000)
+            | Self::GICR_PENDBASER_PTZ;
+
+        pending_table.fill(0);
+        dsb_st();
+
+        let mut gicr = self.gicr_ptr(cpu);
+        field!(gicr, propbaser).write(propbaser);
+        field!(gicr, pendbaser).write(pendbaser);
+        // The redistributor may not support all attributes, in which case the fields read back
+        // differently.
+        if MemoryAttributes::from_gicr_bits(field_shared!(gicr, propbaser).read()) != attributes
+            || MemoryAttributes::from_gicr_bits(field_shared!(gicr, pendbaser).read()) != attributes
+        {
+            return Err(GICRError::UnsupportedAttributes);
+        }
+
+        let ctlr = field_shared!(gicr, ctlr).read();
+        field!(gicr, ctlr).write(ctlr | GicrCtlr::EnableLPIs);
+
+        Ok(())
+    }
+
+    /// Sets the ITS to use for invalidating cached LPI configuration on redistributors which
+    /// don't support direct LPI invalidation, replacing any previously set.
+    ///
+    /// `cpu_collections` must have an entry for each CPU core, indexed by linear CPU index, giving
+    /// a collection which has been mapped to that core's redistributor.
+    ///
+    /// As LPIs are identified to the ITS by device and event ID rather than INTID, invalidation
+    /// through the ITS can't use INV. Instead it uses INVALL on the collection followed by SYNC,
+    /// which makes the redistributor reload its whole cache of LPI configuration and waits for
+    /// this to complete. This happens for every change to an LPI's enable or priority, and for
+    /// every redistributor if no CPU core is specified.
+    ///
+    /// If the ITS returns an error when invalidating then this is returned from
+    /// [`GicV3::enable_interrupt`] or [`GicV3::set_interrupt_priority`].
+    pub fn set_its(&mut self, its: Its<'a>, cpu_collections: &'a [CpuCollection]) {
+        assert_eq!(cpu_collections.len(), self.cpu_count);
+        self.its = Some(its);
+        self.its_collections = cpu_collections;
+    }
+
+    /// Returns the ITS previously passed to [`GicV3::set_its`], if any.
+    pub fn its(&mut self) -> Option<&mut Its<'a>> {
+        self.its.as_mut()
+    }
+
+    /// Returns the entry in the LPI configuration table for the given LPI.
+    fn lpi_config(&self, intid: IntId) -> u8 {
+        let config_table = self
+            .lpi_config_table
+            .as_ref()
+            .expect("No LPI configuration table has been set");
+        config_table.table[config_table.index(intid)]
+    }
+
+    /// Updates the LPI configuration table entry for the given LPI with the given function, then
+    /// invalidates any cached copy of it on the given CPU core's redistributor, or all
+    /// redistributors if `cpu` is `None`.
+    fn modify_lpi_config(
+        &mut self,
+        intid: IntId,
+        cpu: Option<usize>,
+        f: impl FnOnce(u8) -> u8,
+    ) -> Result<(), GICRError> {
+        let config_table = self
+            .lpi_config_table
+            .as_mut()
+            .expect("No LPI configuration table has been set");
+        let index = config_table.index(intid);
+        let entry = &mut config_table.table[index];
+        *entry = f(*entry);
+        // Make sure the new configuration is visible to the GIC before invalidating its cache.
+        dsb_st();
+
+        let cpus = match cpu {
+            Some(cpu) => cpu..cpu + 1,
+            None => 0..self.cpu_count,
+        };
+        for cpu in cpus {
+            if self.gicr_lpi_invalidation_supported(cpu) {
+                let mut gicr = self.gicr_ptr(cpu);
+                field!(gicr, invlpir).write(intid.0.into());
+                Self::gicr_wait_for_sync(&gicr);
+            } else {
+                let (Some(its), Some(collection)) =
+                    (self.its.as_mut(), self.its_collections.get(cpu))
+                else {
+                    return Err(GICRError::NoIts);
+                };
+                its.invall(collection.collection_id)?;
+                its.sync(collection.target)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether the given CPU core's redistributor implements `GICR_INVLPIR`,
+    /// `GICR_INVALLR` and `GICR_SYNCR`.
+    fn gicr_lpi_invalidation_supported(&mut self, cpu: usize) -> bool {
+        self.gicr_typer(cpu).direct_lpis_supported()
+            || field_shared!(self.gicr_ptr(cpu), ctlr)
+                .read()
+                .contains(GicrCtlr::IR)
+    }
+
+    /// Blocks until any invalidate or clear operations on the redistributor are complete.
+    fn gicr_wait_for_sync(gicr_ptr: &SharedMmioPointer<Gicr>) {
+        while field_shared!(gicr_ptr, syncr).read() & Self::GICR_SYNCR_BUSY != 0 {
+            spin_loop();
+        }
+    }
+
+    /// Sets the given LPI to pending on the given CPU core's redistributor, using `GICR_SETLPIR`.
+    ///
+    /// Returns an error if the redistributor doesn't support direct LPIs.
+    pub fn set_lpi_pending(&mut self, cpu: usize, intid: IntId) -> Result<(), GICRError> {
+        self.check_direct_lpi(cpu, intid)?;
+        let mut gicr = self.gicr_ptr(cpu);
+        field!(gicr, setlprir).write(intid.0.into());
+        Ok(())
+    }
+
+    /// Clears the pending state of the given LPI on the given CPU core's redistributor, using
+    /// `GICR_CLRLPIR`, and waits for the operation to complete.
+    ///
+    /// Returns an error if the redistributor doesn't support direct LPIs.
+    pub fn clear_lpi_pending(&mut self, cpu: usize, intid: IntId) -> Result<(), GICRError> {
+        self.check_direct_lpi(cpu, intid)?;
+        let mut gicr = self.gicr_ptr(cpu);
+        field!(gicr, clrlpir).write(intid.0.into());
+        Self::gicr_wait_for_sync(&gicr);
+        Ok(())
+    }
+
+    /// Invalidates any cached configuration of the given LPI on the given CPU core's
+    /// redistributor, using `GICR_INVLPIR`, and waits for the operation to complete.
+    ///
+    /// Returns an error if the redistributor supports neither direct LPIs nor `GICR_CTLR.IR`.
+    pub fn invalidate_lpi(&mut self, cpu: usize, intid: IntId) -> Result<(), GICRError> {
+        self.check_lpi_invalidation(cpu)?;
+        if !intid.is_lpi() {
+            return Err(GICRError::NotAnLpi(intid));
+        }
+        let mut gicr = self.gicr_ptr(cpu);
+        field!(gicr, invlpir).write(intid.0.into());
+        Self::gicr_wait_for_sync(&gicr);
+        Ok(())
+    }
+
+    /// Invalidates all cached LPI configuration on the given CPU core's redistributor, using
+    /// `GICR_INVALLR`, and waits for the operation to complete.
+    ///
+    /// Returns an error if the redistributor supports neither direct LPIs nor `GICR_CTLR.IR`.
+    pub fn invalidate_all_lpis(&mut self, cpu: usize) -> Result<(), GICRError> {
+        self.check_lpi_invalidation(cpu)?;
+        let mut gicr = self.gicr_ptr(cpu);
+        field!(gicr, invallr).write(0);
+        Self::gicr_wait_for_sync(&gicr);
+        Ok(())
+    }
+
+    /// Blocks until any `GICR_CLRLPIR`, `GICR_INVLPIR` or `GICR_INVALLR` operations on the given
+    /// CPU core's redistributor are complete.
+    ///
+    /// Returns an error if the redistributor supports neither direct LPIs nor `GICR_CTLR.IR`.
+    pub fn sync_lpis(&mut self, cpu: usize) -> Result<(), GICRError> {
+        self.check_lpi_invalidation(cpu)?;
+        Self::gicr_wait_for_sync(&self.gicr_ptr(cpu));
+        Ok(())
+    }
+
+    /// Checks that the given CPU core's redistributor implements `GICR_INVLPIR`, `GICR_INVALLR`
+    /// and `GICR_SYNCR`.
+    fn check_lpi_invalidation(&mut self, cpu: usize) -> Result<(), GICRError> {
+        if self.gicr_lpi_invalidation_supported(cpu) {
+            Ok(())
+        } else {
+            Err(GICRError::DirectLpisNotSupported)
+        }
+    }
+
+    /// Checks that the given CPU core's redistributor supports direct LPIs, and that the given
+    /// interrupt ID is an LPI.
+    fn check_direct_lpi(&mut self, cpu: usize, intid: IntId) -> Result<(), GICRError> {
+        if !self.gicr_typer(cpu).direct_lpis_supported() {
+            Err(GICRError::DirectLpisNotSupported)
+        } else if !intid.is_lpi() {
+            Err(GICRError::NotAnLpi(intid))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns whether a vPE is currently resident on the given CPU core's redistributor.
+    pub fn vpe_resident(&mut self, cpu: usize) -> bool {
+        field_shared!(self.vlpi_ptr(cpu), vpendbaser).read() & Self::GICR_VPENDBASER_VALID != 0
+    }
+
+    /// Shares the given GICv4.1 ITS vPE table with the given CPU core's redistributor, by
+    /// programming the GICv4.1 layout of `GICR_VPROPBASER`.
+    ///
+    /// `vpe_table` should be read from the ITS with [`Its::baser`] after the table has been set
+    /// with [`Its::set_table`]. This must be done before any vPE is made resident on the
+    /// redistributor. Redistributors which share a vPE table according to
+    /// [`GicrTyper::common_lpi_affinity`] must all be given the same table.
+    ///
+    /// Returns an error if the redistributor doesn't support GICv4.1, or the table can't be
+    /// described by `GICR_VPROPBASER`.
+    pub fn set_vpe_table(&mut self, cpu: usize, vpe_table: GitsBaser) -> Result<(), GICRError> {
+        if !self.gicr_typer(cpu).rvpeid() {
+            return Err(GICRError::Gicv4_1NotSupported);
+        }
+        if self.vpe_resident(cpu) {
+            return Err(GICRError::VpeAlreadyResident);
+        }
+        let vpropbaser = vpe_table
+            .to_gicv4_1_vpropbaser()
+            .ok_or(GICRError::InvalidVpeTable)?;
+        let mut vlpi = self.vlpi_ptr(cpu);
+        field!(vlpi, vpropbaser).write(vpropbaser);
+        // The redistributor may not support all attributes, in which case the fields read back
+        // differently.
+        if MemoryAttributes::from_gicr_bits(field_shared!(vlpi, vpropbaser).read())
+            != vpe_table.attributes()
+        {
+            field!(vlpi, vpropbaser).write(0);
+            return Err(GICRError::UnsupportedAttributes);
+        }
+        Ok(())
+    }
+
+    /// Makes the given vPE resident on the given CPU core's redistributor, so that virtual LPIs
+    /// for it are delivered directly to the core.
+    ///
+    /// On GICv4.1, as reported by [`GicrTyper::rvpeid`], the redistributor finds the vPE's tables
+    /// through the vPE table set with [`GicV3::set_vpe_table`] and the entry for `vpe_id` which
+    /// the ITS wrote when the vPE was mapped. Otherwise `vpe_id` is ignored and the tables are
+    /// taken from `vpe`.
+    ///
+    /// If the redistributor supports `GICR_VPENDBASER.Dirty` then this waits until it has finished
+    /// parsing the vPE's pending table.
+    ///
+    /// # Safety
+    ///
+    /// The tables which `vpe` refers to must be valid, and must not be used for anything else or
+    /// freed while the vPE is resident.
+    pub unsafe fn make_vpe_resident(
+        &mut self,
+        cpu: usize,
+        vpe_id: u16,
+        vpe: &mut VirtualPe,
+    ) -> Result<(), GICRError> {
+        let typer = self.gicr_typer(cpu);
+        if !typer.virtual_lpis_supported() {
+            return Err(GICRError::VirtualLpisNotSupported);
+        }
+        if self.vpe_resident(cpu) {
+            return Err(GICRError::VpeAlreadyResident);
+        }
+        if typer.rvpeid() {
+            return self.make_gicv4_1_vpe_resident(cpu, vpe_id, vpe);
+        }
+        if !vpe.config_table_physical_address.is_multiple_of(0x1000)
+            || !vpe.pending_table_physical_address.is_multiple_of(0x10000)
+            || !(14..=32).contains(&vpe.id_bits)
+        {
+            return Err(GICRError::InvalidLpiTable);
+        }
+
+        let vpropbaser = vpe.attributes.to_gicr_bits()
+            | (vpe.config_table_physical_address & 0x000f_ffff_ffff_f000)
+            | u64::from(vpe.id_bits - 1);
+        let mut vpendbaser = Self::GICR_VPENDBASER_VALID
+            | vpe.attributes.to_gicr_bits()
+            | (vpe.pending_table_physical_address & 0x000f_ffff_ffff_0000);
+        if vpe.idai {
+            vpendbaser |= Self::GICR_VPENDBASER_IDAI;
+        }
+        if vpe.pending_last {
+            vpendbaser |= Self::GICR_VPENDBASER_PENDING_LAST;
+        }
+
+        let dirty_supported = self.gicr_typer(cpu).dirty_supported();
+        let mut vlpi = self.vlpi_ptr(cpu);
+        field!(vlpi, vpropbaser).write(vpropbaser);
+        // The redistributor may not support all attributes, in which case the fields read back
+        // differently.
+        if MemoryAttributes::from_gicr_bits(field_shared!(vlpi, vpropbaser).read())
+            != vpe.attributes
+        {
+            return Err(GICRError::UnsupportedAttributes);
+        }
+        field!(vlpi, vpendbaser).write(vpendbaser);
+        if dirty_supported {
+            Self::wait_for_vpendbaser_clean(&vlpi);
+        }
+        vpe.pending_table_zeroed = false;
+
+        Ok(())
+    }
+
+    /// Makes the given vPE resident using the GICv4.1 layout of `GICR_VPENDBASER`.
+    fn make_gicv4_1_vpe_resident(
+        &mut self,
+        cpu: usize,
+        vpe_id: u16,
+        vpe: &mut VirtualPe,
+    ) -> Result<(), GICRError> {
+        let mut vlpi = self.vlpi_ptr(cpu);
+        if field_shared!(vlpi, vpropbaser).read() & Self::GICR_VPROPBASER_4_1_VALID == 0 {
+            return Err(GICRError::NoVpeTable);
+        }
+
+        let mut vpendbaser = Self::GICR_VPENDBASER_VALID | u64::from(vpe_id);
+        if vpe.group0_enabled {
+            vpendbaser |= Self::GICR_VPENDBASER_4_1_VGRP0EN;
+        }
+        if vpe.group1_enabled {
+            vpendbaser |= Self::GICR_VPENDBASER_4_1_VGRP1EN;
+        }
+        field!(vlpi, vpendbaser).write(vpendbaser);
+        Self::wait_for_vpendbaser_clean(&vlpi);
+        vpe.pending_table_zeroed = false;
+
+        Ok(())
+    }
+
+    /// Makes the vPE currently resident on the given CPU core's redistributor non-resident, and
+    /// saves its state to `vpe`.
+    ///
+    /// If the redistributor supports `GICR_VPENDBASER.Dirty` then this waits until it has finished
+    /// updating the vPE's pending table, and records whether the vPE has pending interrupts.
+    /// Otherwise the vPE is conservatively assumed to have pending interrupts.
+    ///
+    /// On GICv4.1, if the vPE has a default doorbell then this requests that it be generated when
+    /// an interrupt becomes pending for the vPE.
+    pub fn make_vpe_non_resident(
+        &mut self,
+        cpu: usize,
+        vpe: &mut VirtualPe,
+    ) -> Result<(), GICRError> {
+        let typer = self.gicr_typer(cpu);
+        if !typer.virtual_lpis_supported() {
+            return Err(GICRError::VirtualLpisNotSupported);
+        }
+        if !self.vpe_resident(cpu) {
+            return Err(GICRError::VpeNotResident);
+        }
+
+        let dirty_supported = typer.dirty_supported();
+        let mut vlpi = self.vlpi_ptr(cpu);
+        let mut vpendbaser = field_shared!(vlpi, vpendbaser).read() & !Self::GICR_VPENDBASER_VALID;
+        if typer.rvpeid() && vpe.default_doorbell.is_some() {
+            vpendbaser |= Self::GICR_VPENDBASER_4_1_DB;
+        }
+        field!(vlpi, vpendbaser).write(vpendbaser);
+        if dirty_supported {
+            let vpendbaser = Self::wait_for_vpendbaser_clean(&vlpi);
+            // The GICv4.1 layout has no IDAI field, as the redistributor tracks the vPE itself.
+            vpe.idai = !typer.rvpeid() && vpendbaser & Self::GICR_VPENDBASER_IDAI != 0;
+            vpe.pending_last = vpendbaser & Self::GICR_VPENDBASER_PENDING_LAST != 0;
+        } else {
+            vpe.idai = false;
+            vpe.pending_last = true;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the pending state of the vSGIs of the given GICv4.1 vPE, as a bitmask indexed by SGI
+    /// number, using `GICR_VSGIR` and `GICR_VSGIPENDR` of the given CPU core's redistributor.
+    ///
+    /// Returns an error if the redistributor doesn't support GICv4.1.
+    pub fn vsgi_pending(&mut self, cpu: usize, vpe_id: u16) -> Result<u16, GICRError> {
+        if !self.gicr_typer(cpu).rvpeid() {
+            return Err(GICRError::Gicv4_1NotSupported);
+        }
+        let mut vlpi = self.vlpi_ptr(cpu);
+        field!(vlpi, vsgir).write(vpe_id.into());
+        loop {
+            let vsgipendr = field_shared!(vlpi, vsgipendr).read();
+            if vsgipendr & Self::GICR_VSGIPENDR_BUSY == 0 {
+                return Ok(vsgipendr as u16);
+            }
+            spin_loop();
+        }
+    }
+
+    /// Blocks until `GICR_VPENDBASER.Dirty` is clear, and returns the final value of the register.
+    fn wait_for_vpendbaser_clean(vlpi: &SharedMmioPointer<GicrVlpi>) -> u64 {
+        loop {
+            let vpendbaser = field_shared!(vlpi, vpendbaser).read();
+            if vpendbaser & Self::GICR_VPENDBASER_DIRTY == 0 {
+                return vpendbaser;
+            }
+            spin_loop();
+        }
+    }
+
     /// Informs the GIC redistributor that the core has awakened.
     ///
     /// Blocks until `GICR_WAKER.ChildrenAsleep` is cleared.
@@ -636,6 +2177,10 @@ pub enum SgiTarget {
     /// The SGI is routed to all CPU cores except the current one.
     All,
     /// The SGI is routed to the CPU cores matching the given affinities and list.
+    ///
+    /// Each bit of `target_list` corresponds to an Aff0 value from 0 to 15. A range selector is not
+    /// used, so targets with higher Aff0 values can't be reached even if the CPU interface supports
+    /// [`IccCtlr::RSS`](registers::IccCtlr::RSS).
     List {
         affinity3: u8,
         affinity2: u8,
@@ -644,6 +2189,113 @@ pub enum SgiTarget {
     },
 }
 
+/// The configuration of an interrupt, as returned by [`GicV3::interrupt_config`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InterruptConfig {
+    /// Whether the interrupt is enabled.
+    pub enabled: bool,
+    /// The priority of the interrupt.
+    pub priority: u8,
+    /// The trigger type of the interrupt.
+    pub trigger: Trigger,
+    /// The group of the interrupt.
+    pub group: Group,
+    /// The routing of the interrupt, if it is an SPI or extended SPI.
+    pub route: Option<SpiRoute>,
+}
+
+/// The active priorities of a CPU interface, as saved by [`GicV3::active_priorities`].
+///
+/// Each bit of the `ICC_AP0R<n>_EL1` and `ICC_AP1R<n>_EL1` registers corresponds to a group
+/// priority with an active interrupt. Only the registers implemented for the number of priority
+/// bits in `ICC_CTLR_EL1.PRIbits` are saved and restored.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ActivePriorities {
+    /// The number of registers implemented for each group.
+    count: usize,
+    group0: [u32; 4],
+    group1: [u32; 4],
+}
+
+impl ActivePriorities {
+    /// Returns the number of active priorities registers implemented for each group by a CPU
+    /// interface with the given number of priority bits.
+    pub fn register_count(priority_bits: u32) -> usize {
+        match priority_bits {
+            0..=5 => 1,
+            6 => 2,
+            _ => 4,
+        }
+    }
+
+    /// Returns the saved values of the implemented `ICC_AP0R<n>_EL1` registers.
+    pub fn group0(&self) -> &[u32] {
+        &self.group0[..self.count]
+    }
+
+    /// Returns the saved values of the implemented `ICC_AP1R<n>_EL1` registers.
+    pub fn group1(&self) -> &[u32] {
+        &self.group1[..self.count]
+    }
+
+    /// Returns whether any interrupt was active when the active priorities were saved.
+    pub fn any_active(&self) -> bool {
+        self.group0()
+            .iter()
+            .chain(self.group1())
+            .any(|&bits| bits != 0)
+    }
+}
+
+/// The routing of a shared peripheral interrupt.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SpiRoute {
+    /// The SPI is routed to the CPU core with the given affinity.
+    Affinity {
+        affinity3: u8,
+        affinity2: u8,
+        affinity1: u8,
+        affinity0: u8,
+    },
+    /// The SPI is routed to any one CPU core which is participating in 1 of N distribution.
+    OneOfN,
+}
+
+impl SpiRoute {
+    /// The Interrupt Routing Mode bit of `GICD_IROUTER<n>`.
+    const IRM: u64 = 1 << 31;
+
+    fn to_irouter(self) -> u64 {
+        match self {
+            Self::Affinity {
+                affinity3,
+                affinity2,
+                affinity1,
+                affinity0,
+            } => {
+                u64::from(affinity3) << 32
+                    | u64::from(affinity2) << 16
+                    | u64::from(affinity1) << 8
+                    | u64::from(affinity0)
+            }
+            Self::OneOfN => Self::IRM,
+        }
+    }
+
+    fn from_irouter(irouter: u64) -> Self {
+        if irouter & Self::IRM != 0 {
+            Self::OneOfN
+        } else {
+            Self::Affinity {
+                affinity3: (irouter >> 32) as u8,
+                affinity2: (irouter >> 16) as u8,
+                affinity1: (irouter >> 8) as u8,
+                affinity0: irouter as u8,
+            }
+        }
+    }
+}
+
 /// The target group specification for a software-generated interrupt.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum SgiTargetGroup {
@@ -655,6 +2307,21 @@ pub enum SgiTargetGroup {
     OtherGroup1,
 }
 
+/// The result of [`GicV3::get_and_acknowledge_interrupt`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AcknowledgedInterrupt {
+    /// The interrupt with the given ID was acknowledged.
+    Interrupt(IntId),
+    /// The highest priority pending interrupt is a non-maskable interrupt, which wasn't
+    /// acknowledged.
+    ///
+    /// NMIs are only supported for AArch64, which can acknowledge them with
+    /// [`GicV3::get_and_acknowledge_nmi`].
+    Nmi,
+    /// There is no pending interrupt of sufficient priority.
+    None,
+}
+
 /// An interrupt group, without distinguishing between secure and non-secure.
 ///
 /// This is used to select which group of interrupts to get, acknowledge and end.
@@ -665,3 +2332,523 @@ pub enum InterruptGroup {
     /// Interrupt group 1.
     Group1,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysreg::fake::SYSREGS;
+    use std::boxed::Box;
+
+    /// Zeroed memory standing in for the distributor and redistributor registers of a GIC.
+    struct FakeGic {
+        gicd: Box<Gicd>,
+        gicr: Box<[GicrSgi]>,
+    }
+
+    impl FakeGic {
+        fn new(cpu_count: usize) -> Self {
+            // SAFETY: All the register blocks consist only of integers, for which zero is valid.
+            unsafe {
+                Self {
+                    gicd: Box::new_zeroed().assume_init(),
+                    gicr: Box::new_zeroed_slice(cpu_count).assume_init(),
+                }
+            }
+        }
+
+        /// Sets the value of `GICD_TYPER`.
+        fn set_typer(&mut self, typer: u32) {
+            self.gicd.typer.0 = zerocopy::transmute!(typer);
+        }
+
+        /// Sets the value of the given CPU core's `GICR_TYPER`.
+        fn set_gicr_typer(&mut self, cpu: usize, typer: u64) {
+            self.gicr[cpu].gicr.typer.0 = zerocopy::transmute!(typer);
+        }
+
+        /// Returns a driver instance using the fake registers.
+        fn gic(&mut self) -> GicV3<'_> {
+            // SAFETY: The fake registers are valid and not otherwise accessed while the driver
+            // exists.
+            unsafe {
+                GicV3::new(
+                    &raw mut *self.gicd,
+                    self.gicr.as_mut_ptr(),
+                    self.gicr.len(),
+                    false,
+                )
+            }
+        }
+    }
+
+    /// `GICD_TYPER` with LPIs supported and 16 interrupt ID bits.
+    const TYPER_LPIS: u32 = (1 << 17) | (15 << 19);
+    /// `GICR_TYPER.DirectLPI`.
+    const GICR_TYPER_DIRECT_LPI: u64 = 1 << 3;
+
+    #[test]
+    fn is_lpi() {
+        assert!(!IntId::spi(987).is_lpi());
+        assert!(!IntId::espi(1023).is_lpi());
+        assert!(IntId::lpi(0).is_lpi());
+        assert!(IntId(u32::MAX).is_lpi());
+    }
+
+    #[test]
+    fn lpi_config() {
+        let mut fake = FakeGic::new(2);
+        fake.set_typer(TYPER_LPIS);
+        fake.set_gicr_typer(0, GICR_TYPER_DIRECT_LPI);
+        fake.set_gicr_typer(1, GICR_TYPER_DIRECT_LPI);
+        let mut config_table = [0; 0x2000];
+        let mut gic = fake.gic();
+        // SAFETY: The fake GIC never accesses the table through its physical address.
+        unsafe {
+            gic.set_lpi_config_table(
+                &mut config_table,
+                0x1000,
+                MemoryAttributes::INNER_SHAREABLE_WRITE_BACK,
+            )
+            .unwrap();
+        }
+
+        gic.enable_interrupt(IntId::lpi(5), Some(1), true).unwrap();
+        gic.set_interrupt_priority(IntId::lpi(5), Some(1), 0x42)
+            .unwrap();
+        gic.set_interrupt_priority(IntId::lpi(8191), None, 0x10)
+            .unwrap();
+        assert!(gic.is_enabled(IntId::lpi(5), None));
+        assert_eq!(gic.priority(IntId::lpi(5), None), 0x40);
+        assert!(!gic.is_enabled(IntId::lpi(8191), None));
+        assert_eq!(gic.priority(IntId::lpi(8191), None), 0x10);
+
+        // The cached configuration is invalidated on the given CPU core, or on all of them.
+        assert_eq!(fake.gicr[0].gicr.invlpir.0, u64::from(IntId::lpi(8191).0));
+        assert_eq!(fake.gicr[1].gicr.invlpir.0, u64::from(IntId::lpi(8191).0));
+        assert_eq!(config_table[5], 0x40 | 0b11);
+        assert_eq!(config_table[8191], 0x10 | 0b10);
+    }
+
+    #[test]
+    #[should_panic(expected = "beyond the range covered by the LPI configuration table")]
+    fn lpi_beyond_config_table() {
+        let mut fake = FakeGic::new(1);
+        fake.set_typer(TYPER_LPIS);
+        fake.set_gicr_typer(0, GICR_TYPER_DIRECT_LPI);
+        let mut config_table = [0; 0x2000];
+        let mut gic = fake.gic();
+        // SAFETY: The fake GIC never accesses the table through its physical address.
+        unsafe {
+            gic.set_lpi_config_table(
+                &mut config_table,
+                0x1000,
+                MemoryAttributes::INNER_SHAREABLE_WRITE_BACK,
+            )
+            .unwrap();
+        }
+
+        let _ = gic.enable_interrupt(IntId::lpi(8192), Some(0), true);
+    }
+
+    #[test]
+    fn lpi_invalidation_without_its() {
+        let mut fake = FakeGic::new(1);
+        fake.set_typer(TYPER_LPIS);
+        let mut config_table = [0; 0x2000];
+        let mut gic = fake.gic();
+        // SAFETY: The fake GIC never accesses the table through its physical address.
+        unsafe {
+            gic.set_lpi_config_table(
+                &mut config_table,
+                0x1000,
+                MemoryAttributes::INNER_SHAREABLE_WRITE_BACK,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            gic.enable_interrupt(IntId::lpi(5), Some(0), true),
+            Err(GICRError::NoIts)
+        );
+    }
+
+    #[test]
+    fn direct_lpis() {
+        let mut fake = FakeGic::new(2);
+        fake.set_gicr_typer(1, GICR_TYPER_DIRECT_LPI);
+        let mut gic = fake.gic();
+
+        assert_eq!(
+            gic.set_lpi_pending(0, IntId::lpi(3)),
+            Err(GICRError::DirectLpisNotSupported)
+        );
+        assert_eq!(
+            gic.clear_lpi_pending(0, IntId::lpi(3)),
+            Err(GICRError::DirectLpisNotSupported)
+        );
+        assert_eq!(
+            gic.set_lpi_pending(1, IntId::spi(3)),
+            Err(GICRError::NotAnLpi(IntId::spi(3)))
+        );
+        assert_eq!(
+            gic.clear_lpi_pending(1, IntId::ppi(3)),
+            Err(GICRError::NotAnLpi(IntId::ppi(3)))
+        );
+
+        gic.set_lpi_pending(1, IntId::lpi(3)).unwrap();
+        gic.clear_lpi_pending(1, IntId::lpi(4)).unwrap();
+        assert_eq!(fake.gicr[1].gicr.setlprir.0, 8195);
+        assert_eq!(fake.gicr[1].gicr.clrlpir.0, 8196);
+        assert_eq!(fake.gicr[0].gicr.setlprir.0, 0);
+        assert_eq!(fake.gicr[0].gicr.clrlpir.0, 0);
+    }
+
+    #[test]
+    fn invalidate_lpi() {
+        let mut fake = FakeGic::new(3);
+        fake.set_gicr_typer(1, GICR_TYPER_DIRECT_LPI);
+        // Redistributors without direct LPIs may still implement `GICR_INVLPIR`.
+        fake.gicr[2].gicr.ctlr.0 = GicrCtlr::IR;
+        let mut gic = fake.gic();
+
+        assert_eq!(
+            gic.invalidate_lpi(0, IntId::lpi(6)),
+            Err(GICRError::DirectLpisNotSupported)
+        );
+        assert_eq!(
+            gic.invalidate_all_lpis(0),
+            Err(GICRError::DirectLpisNotSupported)
+        );
+        assert_eq!(
+            gic.invalidate_lpi(1, IntId::sgi(6)),
+            Err(GICRError::NotAnLpi(IntId::sgi(6)))
+        );
+
+        gic.invalidate_lpi(1, IntId::lpi(6)).unwrap();
+        gic.invalidate_lpi(2, IntId::lpi(7)).unwrap();
+        gic.invalidate_all_lpis(2).unwrap();
+        gic.sync_lpis(2).unwrap();
+        assert_eq!(fake.gicr[0].gicr.invlpir.0, 0);
+        assert_eq!(fake.gicr[1].gicr.invlpir.0, 8198);
+        assert_eq!(fake.gicr[2].gicr.invlpir.0, 8199);
+    }
+
+    #[test]
+    fn eppis() {
+        let mut fake = FakeGic::new(2);
+        // 64 extended PPIs on CPU 1, none on CPU 0.
+        fake.set_gicr_typer(1, 2 << 27);
+        let mut gic = fake.gic();
+
+        assert!(IntId::eppi(33).is_private());
+        gic.enable_interrupt(IntId::eppi(33), Some(1), true)
+            .unwrap();
+        gic.enable_interrupt(IntId::eppi(2), Some(1), false)
+            .unwrap();
+        gic.set_interrupt_priority(IntId::eppi(33), Some(1), 0x60)
+            .unwrap();
+        gic.set_trigger(IntId::eppi(17), Some(1), Trigger::Edge);
+        gic.set_group(
+            IntId::eppi(63),
+            Some(1),
+            Group::Secure(SecureIntGroup::Group1S),
+        );
+
+        let sgi = &fake.gicr[1].sgi;
+        assert_eq!(sgi.isenabler_e[1].0, 1 << 1);
+        assert_eq!(sgi.icenabler_e[0].0, 1 << 2);
+        assert_eq!(sgi.ipriorityr_e[33].0, 0x60);
+        // The configuration for extended PPIs follows that for SGIs and PPIs.
+        assert_eq!(sgi.icfgr[3].0, 1 << 3);
+        assert_eq!(sgi.igrpmodr_e[1].0, 1 << 31);
+        // Nothing is written to the distributor or the other redistributor.
+        assert_eq!(fake.gicd.isenabler[1].0, 0);
+        assert_eq!(fake.gicr[0].sgi.isenabler_e[1].0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not implemented by the redistributor for CPU 0")]
+    fn eppi_not_implemented() {
+        let mut fake = FakeGic::new(2);
+        fake.set_gicr_typer(1, 2 << 27);
+        let mut gic = fake.gic();
+
+        let _ = gic.enable_interrupt(IntId::eppi(0), Some(0), true);
+    }
+
+    #[test]
+    fn spi_route_irouter() {
+        let route = SpiRoute::Affinity {
+            affinity3: 0x12,
+            affinity2: 0x34,
+            affinity1: 0x56,
+            affinity0: 0x78,
+        };
+        assert_eq!(route.to_irouter(), 0x12_0034_5678);
+        assert_eq!(SpiRoute::from_irouter(0x12_0034_5678), route);
+        assert_eq!(SpiRoute::OneOfN.to_irouter(), 1 << 31);
+        // The affinity is ignored in 1 of N mode.
+        assert_eq!(SpiRoute::from_irouter(0x12_8034_5678), SpiRoute::OneOfN);
+    }
+
+    #[test]
+    fn spi_route() {
+        let mut fake = FakeGic::new(1);
+        let mut gic = fake.gic();
+        let route = SpiRoute::Affinity {
+            affinity3: 1,
+            affinity2: 2,
+            affinity1: 3,
+            affinity0: 4,
+        };
+
+        gic.set_spi_route(IntId::spi(7), route).unwrap();
+        gic.set_spi_route(IntId::espi(9), SpiRoute::OneOfN).unwrap();
+        assert_eq!(gic.get_spi_route(IntId::spi(7)), Ok(route));
+        assert_eq!(gic.get_spi_route(IntId::espi(9)), Ok(SpiRoute::OneOfN));
+        assert_eq!(
+            gic.set_spi_route(IntId::ppi(7), route),
+            Err(GICDError::NotAnSpi(IntId::ppi(7)))
+        );
+        assert_eq!(fake.gicd.irouter[7].0, 0x1_0002_0304);
+        assert_eq!(fake.gicd.irouter_e[9].0, 1 << 31);
+
+        // GICD_TYPER.No1N
+        fake.set_typer(1 << 25);
+        assert_eq!(
+            fake.gic().set_spi_route(IntId::spi(7), SpiRoute::OneOfN),
+            Err(GICDError::OneOfNNotSupported)
+        );
+    }
+
+    #[test]
+    fn pending_and_active() {
+        let mut fake = FakeGic::new(2);
+        fake.set_gicr_typer(1, 1 << 27);
+        // Other interrupts covered by the same registers are already pending or active.
+        fake.gicd.ispendr[1].0 = 0xff00;
+        fake.gicd.icpendr[1].0 = 0xff00;
+        fake.gicr[1].sgi.isactiver0.0 = 0xff00;
+        let mut gic = fake.gic();
+
+        gic.set_pending(IntId::spi(3), None);
+        gic.clear_pending(IntId::spi(4), None);
+        gic.set_active(IntId::ppi(5), Some(1));
+        gic.clear_active(IntId::sgi(6), Some(1));
+        gic.set_pending(IntId::espi(40), None);
+        gic.clear_active(IntId::eppi(7), Some(1));
+        assert!(gic.is_pending(IntId::spi(3), None));
+        assert!(!gic.is_pending(IntId::spi(4), None));
+        assert!(gic.is_active(IntId::ppi(5), Some(1)));
+        assert!(!gic.is_active(IntId::ppi(5), Some(0)));
+
+        // Only the bit for the given interrupt is written, so other interrupts are unaffected.
+        assert_eq!(fake.gicd.ispendr[1].0, 1 << 3);
+        assert_eq!(fake.gicd.icpendr[1].0, 1 << 4);
+        assert_eq!(fake.gicr[1].sgi.isactiver0.0, 1 << 21);
+        assert_eq!(fake.gicr[1].sgi.icactiver0.0, 1 << 6);
+        assert_eq!(fake.gicd.ispendr_e[1].0, 1 << 8);
+        assert_eq!(fake.gicr[1].sgi.icactive_e[0].0, 1 << 7);
+        assert_eq!(fake.gicr[0].sgi.isactiver0.0, 0);
+    }
+
+    #[test]
+    fn interrupt_config() {
+        let mut fake = FakeGic::new(2);
+        let mut gic = fake.gic();
+        let route = SpiRoute::Affinity {
+            affinity3: 0,
+            affinity2: 0,
+            affinity1: 1,
+            affinity0: 0,
+        };
+
+        gic.set_interrupt_priority(IntId::spi(5), None, 0x30)
+            .unwrap();
+        gic.set_trigger(IntId::spi(5), None, Trigger::Edge);
+        gic.set_group(IntId::spi(5), None, Group::Group1NS);
+        gic.set_spi_route(IntId::spi(5), route).unwrap();
+        gic.enable_interrupt(IntId::spi(5), None, true).unwrap();
+        gic.set_interrupt_priority(IntId::ppi(3), Some(1), 0x90)
+            .unwrap();
+        gic.set_group(
+            IntId::ppi(3),
+            Some(1),
+            Group::Secure(SecureIntGroup::Group1S),
+        );
+
+        assert_eq!(
+            gic.interrupt_config(IntId::spi(5), None),
+            InterruptConfig {
+                enabled: true,
+                priority: 0x30,
+                trigger: Trigger::Edge,
+                group: Group::Group1NS,
+                route: Some(route),
+            }
+        );
+        assert_eq!(
+            gic.interrupt_config(IntId::ppi(3), Some(1)),
+            InterruptConfig {
+                enabled: false,
+                priority: 0x90,
+                trigger: Trigger::Level,
+                group: Group::Secure(SecureIntGroup::Group1S),
+                route: None,
+            }
+        );
+        assert_eq!(gic.interrupt_config(IntId::ppi(3), Some(0)).priority, 0);
+    }
+
+    #[test]
+    fn acknowledge_interrupt() {
+        let _lock = crate::sysreg::fake::lock_for_test();
+        SYSREGS.lock().unwrap().icc_iar0_el1 = IntId::ppi(4).0;
+        SYSREGS.lock().unwrap().icc_iar1_el1 = IntId::SPECIAL_NONE.0;
+
+        assert_eq!(
+            GicV3::get_and_acknowledge_interrupt(InterruptGroup::Group0),
+            AcknowledgedInterrupt::Interrupt(IntId::ppi(4))
+        );
+        assert_eq!(
+            GicV3::get_and_acknowledge_interrupt(InterruptGroup::Group1),
+            AcknowledgedInterrupt::None
+        );
+    }
+
+    #[test]
+    fn acknowledge_nmi() {
+        let _lock = crate::sysreg::fake::lock_for_test();
+        SYSREGS.lock().unwrap().icc_iar1_el1 = IntId::SPECIAL_NMI.0;
+        SYSREGS.lock().unwrap().icc_nmiar1_el1 = IntId::spi(8).0;
+
+        assert_eq!(
+            GicV3::get_and_acknowledge_interrupt(InterruptGroup::Group1),
+            AcknowledgedInterrupt::Nmi
+        );
+        assert_eq!(GicV3::get_and_acknowledge_nmi(), Some(IntId::spi(8)));
+
+        SYSREGS.lock().unwrap().icc_nmiar1_el1 = IntId::SPECIAL_NONE.0;
+        assert_eq!(GicV3::get_and_acknowledge_nmi(), None);
+    }
+
+    #[test]
+    fn message_spis() {
+        let mut fake = FakeGic::new(1);
+        let mut gic = fake.gic();
+        assert_eq!(
+            gic.set_message_spi(IntId::spi(2)),
+            Err(GICDError::MessageBasedSpisNotSupported)
+        );
+
+        // GICD_TYPER.MBIS
+        fake.set_typer(1 << 16);
+        let mut gic = fake.gic();
+        gic.set_message_spi(IntId::spi(2)).unwrap();
+        gic.clear_message_spi(IntId::espi(3)).unwrap();
+        assert_eq!(
+            gic.set_message_spi(IntId::ppi(2)),
+            Err(GICDError::NotAnSpi(IntId::ppi(2)))
+        );
+        assert_eq!(
+            gic.spi_msi_message(IntId::espi(3), 0x800_0000),
+            Ok(MsiMessage {
+                address: 0x800_0040,
+                data: 4099,
+            })
+        );
+        assert_eq!(
+            gic.spi_msi_message(IntId::lpi(3), 0x800_0000),
+            Err(GICDError::NotAnSpi(IntId::lpi(3)))
+        );
+        assert_eq!(fake.gicd.setspi_nsr.0, 34);
+        assert_eq!(fake.gicd.clrspi_nsr.0, 4099);
+    }
+
+    #[test]
+    fn split_eoi() {
+        let _lock = crate::sysreg::fake::lock_for_test();
+        // Other fields of ICC_CTLR_EL1 are left unchanged.
+        SYSREGS.lock().unwrap().icc_ctlr_el1 = (IccCtlr::CBPR | IccCtlr::A3V).bits();
+
+        GicV3::enable_split_eoi(true);
+        assert_eq!(
+            SYSREGS.lock().unwrap().icc_ctlr_el1,
+            (IccCtlr::CBPR | IccCtlr::A3V | IccCtlr::EOImode).bits()
+        );
+
+        GicV3::end_interrupt(IntId::spi(3), InterruptGroup::Group1);
+        GicV3::deactivate_interrupt(IntId::spi(3));
+        assert_eq!(SYSREGS.lock().unwrap().icc_eoir1_el1, 35);
+        assert_eq!(SYSREGS.lock().unwrap().icc_dir_el1, 35);
+
+        GicV3::enable_split_eoi(false);
+        assert_eq!(
+            SYSREGS.lock().unwrap().icc_ctlr_el1,
+            (IccCtlr::CBPR | IccCtlr::A3V).bits()
+        );
+    }
+
+    #[test]
+    fn binary_point() {
+        let _lock = crate::sysreg::fake::lock_for_test();
+        // 5 priority bits.
+        SYSREGS.lock().unwrap().icc_ctlr_el1 = 4 << 8;
+
+        GicV3::set_binary_point(InterruptGroup::Group0, 2);
+        GicV3::set_binary_point(InterruptGroup::Group1, 5);
+        assert_eq!(SYSREGS.lock().unwrap().icc_bpr0_el1, 2);
+        assert_eq!(SYSREGS.lock().unwrap().icc_bpr1_el1, 5);
+        assert_eq!(GicV3::binary_point(InterruptGroup::Group0), 2);
+        assert_eq!(GicV3::binary_point(InterruptGroup::Group1), 5);
+        assert_eq!(GicV3::preemption_levels(InterruptGroup::Group0), 32);
+        assert_eq!(GicV3::preemption_levels(InterruptGroup::Group1), 8);
+    }
+
+    #[test]
+    fn active_priorities_register_count() {
+        assert_eq!(ActivePriorities::register_count(5), 1);
+        assert_eq!(ActivePriorities::register_count(6), 2);
+        assert_eq!(ActivePriorities::register_count(7), 4);
+    }
+
+    #[test]
+    fn save_restore_active_priorities() {
+        let _lock = crate::sysreg::fake::lock_for_test();
+        // 6 priority bits, so only ICC_AP0R<0-1>_EL1 and ICC_AP1R<0-1>_EL1 are implemented.
+        {
+            let mut sysregs = SYSREGS.lock().unwrap();
+            sysregs.icc_ctlr_el1 = 5 << 8;
+            sysregs.icc_ap0r0_el1 = 0x1;
+            sysregs.icc_ap0r1_el1 = 0x2;
+            sysregs.icc_ap0r2_el1 = 0x3;
+            sysregs.icc_ap1r0_el1 = 0x10;
+            sysregs.icc_ap1r1_el1 = 0x20;
+            sysregs.icc_ap1r3_el1 = 0x40;
+        }
+
+        let saved = GicV3::active_priorities();
+        assert_eq!(saved.group0(), [0x1, 0x2]);
+        assert_eq!(saved.group1(), [0x10, 0x20]);
+        assert!(saved.any_active());
+
+        {
+            let mut sysregs = SYSREGS.lock().unwrap();
+            sysregs.icc_ap0r0_el1 = 0;
+            sysregs.icc_ap0r1_el1 = 0;
+            sysregs.icc_ap1r0_el1 = 0;
+            sysregs.icc_ap1r1_el1 = 0;
+        }
+        assert!(!GicV3::active_priorities().any_active());
+
+        GicV3::restore_active_priorities(&saved);
+        let sysregs = SYSREGS.lock().unwrap();
+        assert_eq!(sysregs.icc_ap0r0_el1, 0x1);
+        assert_eq!(sysregs.icc_ap0r1_el1, 0x2);
+        assert_eq!(sysregs.icc_ap1r0_el1, 0x10);
+        assert_eq!(sysregs.icc_ap1r1_el1, 0x20);
+        // Unimplemented registers aren't written.
+        assert_eq!(sysregs.icc_ap0r2_el1, 0x3);
+        assert_eq!(sysregs.icc_ap1r3_el1, 0x40);
+    }
+}
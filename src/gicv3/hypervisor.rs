@@ -0,0 +1,571 @@
+// Copyright 2026 The arm-gic Authors.
+// This project is dual-licensed under Apache 2.0 and MIT terms.
+// See LICENSE-APACHE and LICENSE-MIT for details.
+
+//! Support for the GICv3 virtual CPU interface, for use by a hypervisor running at EL2.
+//!
+//! Virtual interrupts are presented to a VM through the list registers (`ICH_LR<n>_EL2`), which
+//! are managed by [`ListRegisters`]. The other `ICH_*_EL2` registers are accessed through the
+//! functions in this module.
+
+use crate::IntId;
+pub use crate::hypervisor::{
+    EoiedInterrupts, HypervisorError, ListRegisterState, VirtualInterrupt,
+};
+use crate::sysreg::{
+    read_ich_ap0r0_el2, read_ich_ap0r1_el2, read_ich_ap0r2_el2, read_ich_ap0r3_el2,
+    read_ich_ap1r0_el2, read_ich_ap1r1_el2, read_ich_ap1r2_el2, read_ich_ap1r3_el2,
+    read_ich_eisr_el2, read_ich_elrsr_el2, read_ich_hcr_el2, read_ich_lr0_el2, read_ich_lr1_el2,
+    read_ich_lr2_el2, read_ich_lr3_el2, read_ich_lr4_el2, read_ich_lr5_el2, read_ich_lr6_el2,
+    read_ich_lr7_el2, read_ich_lr8_el2, read_ich_lr9_el2, read_ich_lr10_el2, read_ich_lr11_el2,
+    read_ich_lr12_el2, read_ich_lr13_el2, read_ich_lr14_el2, read_ich_lr15_el2, read_ich_misr_el2,
+    read_ich_vmcr_el2, read_ich_vtr_el2, write_ich_ap0r0_el2, write_ich_ap0r1_el2,
+    write_ich_ap0r2_el2, write_ich_ap0r3_el2, write_ich_ap1r0_el2, write_ich_ap1r1_el2,
+    write_ich_ap1r2_el2, write_ich_ap1r3_el2, write_ich_hcr_el2, write_ich_lr0_el2,
+    write_ich_lr1_el2, write_ich_lr2_el2, write_ich_lr3_el2, write_ich_lr4_el2, write_ich_lr5_el2,
+    write_ich_lr6_el2, write_ich_lr7_el2, write_ich_lr8_el2, write_ich_lr9_el2, write_ich_lr10_el2,
+    write_ich_lr11_el2, write_ich_lr12_el2, write_ich_lr13_el2, write_ich_lr14_el2,
+    write_ich_lr15_el2, write_ich_vmcr_el2,
+};
+use bitflags::bitflags;
+use core::fmt::{self, Debug, Formatter};
+
+/// The result of handling a maintenance interrupt with [`ListRegisters::handle_maintenance`].
+pub type Maintenance = crate::hypervisor::Maintenance<IchMisr>;
+
+/// Interrupt controller hyp control register value.
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct IchHcr(u32);
+
+bitflags! {
+    impl IchHcr: u32 {
+        const DVIM = 1 << 15;
+        const TDIR = 1 << 14;
+        const TSEI = 1 << 13;
+        const TALL1 = 1 << 12;
+        const TALL0 = 1 << 11;
+        const TC = 1 << 10;
+        const vSGIEOICount = 1 << 8;
+        const VGrp1DIE = 1 << 7;
+        const VGrp1EIE = 1 << 6;
+        const VGrp0DIE = 1 << 5;
+        const VGrp0EIE = 1 << 4;
+        const NPIE = 1 << 3;
+        const LRENPIE = 1 << 2;
+        const UIE = 1 << 1;
+        const En = 1 << 0;
+    }
+}
+
+impl IchHcr {
+    /// Returns the number of EOIs which didn't match a list register, from the EOIcount field.
+    pub fn eoi_count(self) -> u32 {
+        self.bits() >> 27
+    }
+}
+
+impl Debug for IchHcr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "IchHcr(")?;
+        bitflags::parser::to_writer(self, &mut *f)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+/// Interrupt controller maintenance interrupt state register value.
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct IchMisr(u32);
+
+bitflags! {
+    impl IchMisr: u32 {
+        const VGrp1D = 1 << 7;
+        const VGrp1E = 1 << 6;
+        const VGrp0D = 1 << 5;
+        const VGrp0E = 1 << 4;
+        const NP = 1 << 3;
+        const LRENP = 1 << 2;
+        const U = 1 << 1;
+        const EOI = 1 << 0;
+    }
+}
+
+impl Debug for IchMisr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "IchMisr(")?;
+        bitflags::parser::to_writer(self, &mut *f)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+/// Interrupt controller VGIC type register value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct IchVtr(u32);
+
+impl IchVtr {
+    /// Returns the number of virtual priority bits implemented.
+    pub fn priority_bits(self) -> u32 {
+        (self.0 >> 29) + 1
+    }
+
+    /// Returns the number of virtual preemption bits implemented.
+    pub fn preemption_bits(self) -> u32 {
+        ((self.0 >> 26) & 0b111) + 1
+    }
+
+    /// Returns the number of virtual interrupt ID bits supported.
+    pub fn id_bits(self) -> u32 {
+        if (self.0 >> 23) & 0b111 == 0b001 {
+            24
+        } else {
+            16
+        }
+    }
+
+    /// Returns whether the virtual CPU interface supports generating SEIs.
+    pub fn seis_supported(self) -> bool {
+        self.0 & (1 << 22) != 0
+    }
+
+    /// Returns whether the virtual CPU interface supports nonzero values of affinity level 3.
+    pub fn affinity_3_supported(self) -> bool {
+        self.0 & (1 << 21) != 0
+    }
+
+    /// Returns whether direct injection of virtual LPIs is supported.
+    pub fn direct_virtual_lpis_supported(self) -> bool {
+        self.0 & (1 << 20) == 0
+    }
+
+    /// Returns whether trapping of `ICC_DIR_EL1` writes separately from other registers is
+    /// supported.
+    pub fn separate_dir_trapping_supported(self) -> bool {
+        self.0 & (1 << 19) != 0
+    }
+
+    /// Returns the number of list registers implemented.
+    pub fn list_register_count(self) -> usize {
+        ((self.0 & 0b11111) + 1) as usize
+    }
+}
+
+/// A list register value.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct ListRegister(u64);
+
+impl ListRegister {
+    const STATE_SHIFT: u64 = 62;
+    const HW: u64 = 1 << 61;
+    const GROUP: u64 = 1 << 60;
+    const PRIORITY_SHIFT: u64 = 48;
+    const PINTID_SHIFT: u64 = 32;
+    const PINTID_MASK: u64 = 0x1fff;
+    const EOI: u64 = 1 << 41;
+    const VINTID_MASK: u64 = 0xffff_ffff;
+
+    /// An empty list register.
+    pub const INVALID: Self = Self(0);
+
+    /// Returns a list register value for the given virtual interrupt in the pending state.
+    ///
+    /// If the virtual interrupt isn't linked to a physical interrupt then a maintenance interrupt
+    /// is requested when it is deactivated, so that [`ListRegisters::handle_maintenance`] can
+    /// report it.
+    pub fn pending(interrupt: VirtualInterrupt) -> Self {
+        let mut value = ((ListRegisterState::Pending as u64) << Self::STATE_SHIFT)
+            | (u64::from(interrupt.priority) << Self::PRIORITY_SHIFT)
+            | (u64::from(interrupt.intid.0) & Self::VINTID_MASK);
+        if interrupt.group1 {
+            value |= Self::GROUP;
+        }
+        if let Some(physical) = interrupt.physical {
+            value |= Self::HW | ((u64::from(physical.0) & Self::PINTID_MASK) << Self::PINTID_SHIFT);
+        } else {
+            value |= Self::EOI;
+        }
+        Self(value)
+    }
+
+    /// Returns the raw value of the list register.
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the state of the virtual interrupt.
+    pub fn state(self) -> ListRegisterState {
+        ListRegisterState::from_bits((self.0 >> Self::STATE_SHIFT) as u32)
+    }
+
+    /// Returns the virtual interrupt ID.
+    pub fn intid(self) -> IntId {
+        IntId((self.0 & Self::VINTID_MASK) as u32)
+    }
+
+    /// Returns the priority of the virtual interrupt.
+    pub fn priority(self) -> u8 {
+        (self.0 >> Self::PRIORITY_SHIFT) as u8
+    }
+
+    /// Returns whether the virtual interrupt is in group 1.
+    pub fn group1(self) -> bool {
+        self.0 & Self::GROUP != 0
+    }
+
+    /// Returns the physical interrupt which the virtual interrupt is linked to, if any.
+    pub fn physical(self) -> Option<IntId> {
+        if self.0 & Self::HW != 0 {
+            Some(IntId(
+                ((self.0 >> Self::PINTID_SHIFT) & Self::PINTID_MASK) as u32,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether a maintenance interrupt is requested when the virtual interrupt is
+    /// deactivated.
+    pub fn eoi_maintenance(self) -> bool {
+        self.0 & Self::HW == 0 && self.0 & Self::EOI != 0
+    }
+}
+
+/// Reads `ICH_HCR_EL2`.
+pub fn read_hcr() -> IchHcr {
+    IchHcr::from_bits_retain(read_ich_hcr_el2())
+}
+
+/// Writes `ICH_HCR_EL2`.
+pub fn write_hcr(value: IchHcr) {
+    write_ich_hcr_el2(value.bits());
+}
+
+/// Reads `ICH_VTR_EL2`.
+pub fn read_vtr() -> IchVtr {
+    IchVtr(read_ich_vtr_el2())
+}
+
+/// Reads `ICH_VMCR_EL2`, which holds the state of the VM's virtual CPU interface.
+pub fn read_vmcr() -> u32 {
+    read_ich_vmcr_el2()
+}
+
+/// Writes `ICH_VMCR_EL2`, which holds the state of the VM's virtual CPU interface.
+pub fn write_vmcr(value: u32) {
+    write_ich_vmcr_el2(value);
+}
+
+/// Reads `ICH_MISR_EL2`, to find the reasons for a maintenance interrupt.
+pub fn read_misr() -> IchMisr {
+    IchMisr::from_bits_retain(read_ich_misr_el2())
+}
+
+/// Reads `ICH_EISR_EL2`, which has a bit set for each list register with an EOI maintenance
+/// interrupt.
+pub fn read_eisr() -> u32 {
+    read_ich_eisr_el2()
+}
+
+/// Reads `ICH_ELRSR_EL2`, which has a bit set for each list register which doesn't contain a valid
+/// interrupt.
+pub fn read_elrsr() -> u32 {
+    read_ich_elrsr_el2()
+}
+
+/// Reads `ICH_AP0R<n>_EL2`.
+///
+/// Panics if `n` is greater than 3.
+pub fn read_ap0r(n: usize) -> u32 {
+    match n {
+        0 => read_ich_ap0r0_el2(),
+        1 => read_ich_ap0r1_el2(),
+        2 => read_ich_ap0r2_el2(),
+        3 => read_ich_ap0r3_el2(),
+        _ => panic!("Invalid ICH_AP0R<n>_EL2 index {n}"),
+    }
+}
+
+/// Writes `ICH_AP0R<n>_EL2`.
+///
+/// Panics if `n` is greater than 3.
+pub fn write_ap0r(n: usize, value: u32) {
+    match n {
+        0 => write_ich_ap0r0_el2(value),
+        1 => write_ich_ap0r1_el2(value),
+        2 => write_ich_ap0r2_el2(value),
+        3 => write_ich_ap0r3_el2(value),
+        _ => panic!("Invalid ICH_AP0R<n>_EL2 index {n}"),
+    }
+}
+
+/// Reads `ICH_AP1R<n>_EL2`.
+///
+/// Panics if `n` is greater than 3.
+pub fn read_ap1r(n: usize) -> u32 {
+    match n {
+        0 => read_ich_ap1r0_el2(),
+        1 => read_ich_ap1r1_el2(),
+        2 => read_ich_ap1r2_el2(),
+        3 => read_ich_ap1r3_el2(),
+        _ => panic!("Invalid ICH_AP1R<n>_EL2 index {n}"),
+    }
+}
+
+/// Writes `ICH_AP1R<n>_EL2`.
+///
+/// Panics if `n` is greater than 3.
+pub fn write_ap1r(n: usize, value: u32) {
+    match n {
+        0 => write_ich_ap1r0_el2(value),
+        1 => write_ich_ap1r1_el2(value),
+        2 => write_ich_ap1r2_el2(value),
+        3 => write_ich_ap1r3_el2(value),
+        _ => panic!("Invalid ICH_AP1R<n>_EL2 index {n}"),
+    }
+}
+
+/// Reads `ICH_LR<n>_EL2`.
+///
+/// Panics if `n` is greater than 15.
+pub fn read_lr(n: usize) -> ListRegister {
+    ListRegister(match n {
+        0 => read_ich_lr0_el2(),
+        1 => read_ich_lr1_el2(),
+        2 => read_ich_lr2_el2(),
+        3 => read_ich_lr3_el2(),
+        4 => read_ich_lr4_el2(),
+        5 => read_ich_lr5_el2(),
+        6 => read_ich_lr6_el2(),
+        7 => read_ich_lr7_el2(),
+        8 => read_ich_lr8_el2(),
+        9 => read_ich_lr9_el2(),
+        10 => read_ich_lr10_el2(),
+        11 => read_ich_lr11_el2(),
+        12 => read_ich_lr12_el2(),
+        13 => read_ich_lr13_el2(),
+        14 => read_ich_lr14_el2(),
+        15 => read_ich_lr15_el2(),
+        _ => panic!("Invalid ICH_LR<n>_EL2 index {n}"),
+    })
+}
+
+/// Writes `ICH_LR<n>_EL2`.
+///
+/// Panics if `n` is greater than 15.
+pub fn write_lr(n: usize, value: ListRegister) {
+    let value = value.0;
+    match n {
+        0 => write_ich_lr0_el2(value),
+        1 => write_ich_lr1_el2(value),
+        2 => write_ich_lr2_el2(value),
+        3 => write_ich_lr3_el2(value),
+        4 => write_ich_lr4_el2(value),
+        5 => write_ich_lr5_el2(value),
+        6 => write_ich_lr6_el2(value),
+        7 => write_ich_lr7_el2(value),
+        8 => write_ich_lr8_el2(value),
+        9 => write_ich_lr9_el2(value),
+        10 => write_ich_lr10_el2(value),
+        11 => write_ich_lr11_el2(value),
+        12 => write_ich_lr12_el2(value),
+        13 => write_ich_lr13_el2(value),
+        14 => write_ich_lr14_el2(value),
+        15 => write_ich_lr15_el2(value),
+        _ => panic!("Invalid ICH_LR<n>_EL2 index {n}"),
+    }
+}
+
+/// Manages the list registers of the current CPU core's virtual CPU interface.
+#[derive(Debug)]
+pub struct ListRegisters {
+    /// The number of list registers implemented.
+    count: usize,
+}
+
+impl ListRegisters {
+    /// Creates a new list register manager for the current CPU core, clearing all list registers.
+    pub fn new() -> Self {
+        let list_registers = Self {
+            count: read_vtr().list_register_count(),
+        };
+        list_registers.clear();
+        list_registers
+    }
+
+    /// Returns the number of list registers implemented.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Enables or disables the virtual CPU interface, by setting `ICH_HCR_EL2.En`.
+    pub fn enable(&mut self, enable: bool) {
+        let hcr = read_hcr();
+        write_hcr(if enable {
+            hcr | IchHcr::En
+        } else {
+            hcr - IchHcr::En
+        });
+    }
+
+    /// Invalidates all list registers.
+    pub fn clear(&self) {
+        for n in 0..self.count {
+            write_lr(n, ListRegister::INVALID);
+        }
+    }
+
+    /// Returns a bitmask of list registers which don't contain a valid interrupt.
+    fn empty_mask(&self) -> u32 {
+        read_elrsr() & (u32::MAX >> (32 - self.count))
+    }
+
+    /// Queues the given virtual interrupt as pending in a free list register, and returns the
+    /// index of the list register used.
+    ///
+    /// Returns an error if all list registers are in use, if the virtual interrupt is already in a
+    /// list register, or if it is linked to a physical interrupt which isn't a PPI or SPI.
+    pub fn inject(&mut self, interrupt: VirtualInterrupt) -> Result<usize, HypervisorError> {
+        if let Some(physical) = interrupt.physical
+            && !(physical.is_ppi() || physical.is_eppi() || physical.is_spi() || physical.is_espi())
+        {
+            return Err(HypervisorError::InvalidPhysicalInterrupt(physical));
+        }
+
+        let empty = self.empty_mask();
+        for n in 0..self.count {
+            if empty & (1 << n) == 0 && read_lr(n).intid() == interrupt.intid {
+                return Err(HypervisorError::AlreadyQueued(interrupt.intid));
+            }
+        }
+        if empty == 0 {
+            return Err(HypervisorError::NoFreeListRegister);
+        }
+
+        let n = empty.trailing_zeros() as usize;
+        write_lr(n, ListRegister::pending(interrupt));
+        Ok(n)
+    }
+
+    /// Handles a maintenance interrupt.
+    ///
+    /// List registers whose interrupts have been deactivated by the VM are invalidated, and the
+    /// corresponding virtual interrupt IDs returned along with the reasons for the maintenance
+    /// interrupt.
+    pub fn handle_maintenance(&mut self) -> Maintenance {
+        let misr = read_misr();
+        let eisr = read_eisr() & (u32::MAX >> (32 - self.count));
+
+        let mut intids = [IntId(0); 64];
+        for (n, intid) in intids.iter_mut().enumerate().take(self.count) {
+            if eisr & (1 << n) != 0 {
+                *intid = read_lr(n).intid();
+                write_lr(n, ListRegister::INVALID);
+            }
+        }
+
+        Maintenance {
+            misr,
+            eoied: EoiedInterrupts {
+                mask: eisr.into(),
+                intids,
+            },
+        }
+    }
+}
+
+impl Default for ListRegisters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysreg::fake::SYSREGS;
+
+    #[test]
+    fn list_register_pending() {
+        let lr = ListRegister::pending(VirtualInterrupt {
+            intid: IntId::spi(3),
+            priority: 0x80,
+            group1: true,
+            physical: None,
+            source_cpu: 0,
+        });
+        assert_eq!(lr.bits(), 0x5080_0200_0000_0023);
+        assert_eq!(lr.state(), ListRegisterState::Pending);
+        assert_eq!(lr.intid(), IntId::spi(3));
+        assert_eq!(lr.priority(), 0x80);
+        assert!(lr.group1());
+        assert_eq!(lr.physical(), None);
+        assert!(lr.eoi_maintenance());
+    }
+
+    #[test]
+    fn list_register_hardware() {
+        let lr = ListRegister::pending(VirtualInterrupt {
+            intid: IntId::ppi(11),
+            priority: 0xa0,
+            group1: false,
+            physical: Some(IntId::ppi(10)),
+            source_cpu: 0,
+        });
+        assert_eq!(lr.bits(), 0x60a0_001a_0000_001b);
+        assert_eq!(lr.physical(), Some(IntId::ppi(10)));
+        assert!(!lr.group1());
+        assert!(!lr.eoi_maintenance());
+    }
+
+    #[test]
+    fn ich_vtr() {
+        // 16 list registers, 5 priority bits, 5 preemption bits, 16 ID bits, no direct LPIs.
+        let vtr = IchVtr(0x9010_000f);
+        assert_eq!(vtr.list_register_count(), 16);
+        assert_eq!(vtr.priority_bits(), 5);
+        assert_eq!(vtr.preemption_bits(), 5);
+        assert_eq!(vtr.id_bits(), 16);
+        assert!(!vtr.direct_virtual_lpis_supported());
+    }
+
+    #[test]
+    fn inject_physical() {
+        let _lock = crate::sysreg::fake::lock_for_test();
+        // Four list registers, all empty.
+        SYSREGS.lock().unwrap().ich_vtr_el2 = 3;
+        SYSREGS.lock().unwrap().ich_elrsr_el2 = 0b1111;
+        let mut list_registers = ListRegisters::new();
+        let linked = |physical| VirtualInterrupt {
+            intid: IntId::spi(1),
+            priority: 0x80,
+            group1: true,
+            physical: Some(physical),
+            source_cpu: 0,
+        };
+
+        for physical in [
+            IntId::sgi(1),
+            IntId::SPECIAL_NONE,
+            IntId::lpi(0),
+            IntId(5120),
+        ] {
+            assert_eq!(
+                list_registers.inject(linked(physical)),
+                Err(HypervisorError::InvalidPhysicalInterrupt(physical))
+            );
+        }
+        assert_eq!(SYSREGS.lock().unwrap().ich_lr0_el2, 0);
+
+        for physical in [IntId::ppi(1), IntId::eppi(1), IntId::spi(1), IntId::espi(1)] {
+            assert_eq!(list_registers.inject(linked(physical)), Ok(0));
+            assert_eq!(
+                ListRegister(SYSREGS.lock().unwrap().ich_lr0_el2).physical(),
+                Some(physical)
+            );
+        }
+    }
+}
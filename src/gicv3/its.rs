@@ -0,0 +1,1109 @@
+// Copyright 2026 The arm-gic Authors.
+// This project is dual-licensed under Apache 2.0 and MIT terms.
+// See LICENSE-APACHE and LICENSE-MIT for details.
+
+//! Driver for the GICv3 Interrupt Translation Service (ITS).
+//!
+//! The ITS translates writes to `GITS_TRANSLATER` from peripherals (such as PCIe MSIs) into LPIs.
+//! It is configured by writing commands to a command queue in memory, which are provided by the
+//! typed methods of [`Its`].
+//!
+//! On GICv4 the ITS can also map events directly to virtual LPIs of a virtual PE, which are
+//! delivered without hypervisor intervention while the vPE is resident on a redistributor.
+
+use super::VirtualPe;
+use super::registers::{
+    GicrTyper, Gits, GitsBaser, GitsCtlr, GitsTyper, ItsPageSize, ItsTableType, MemoryAttributes,
+};
+use crate::{IntId, dsb_st};
+use core::{hint::spin_loop, ptr::NonNull};
+use safe_mmio::{UniqueMmioPointer, field, field_shared};
+use thiserror::Error;
+
+/// An error which may be returned from operations on a GIC Interrupt Translation Service.
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ITSError {
+    #[error("ITS is enabled so can't be reconfigured")]
+    AlreadyEnabled,
+    #[error("Command queue must be 4 KiB aligned and a multiple of 4 KiB, up to 1 MiB")]
+    InvalidCommandQueue,
+    #[error("ITS has no {0:?} table")]
+    NoSuchTable(ItsTableType),
+    #[error("Table memory must be aligned to and a multiple of the page size, up to 256 pages")]
+    InvalidTable,
+    #[error("ITS doesn't support the requested page size")]
+    UnsupportedPageSize,
+    #[error("ITS doesn't support the requested memory attributes")]
+    UnsupportedAttributes,
+    #[error("Device ID {0} is out of range")]
+    DeviceIdOutOfRange(u32),
+    #[error("Event ID {0} is out of range")]
+    EventIdOutOfRange(u32),
+    #[error("Collection ID {0} is out of range")]
+    CollectionIdOutOfRange(u16),
+    #[error("Interrupt translation table is too small or not 256 byte aligned")]
+    InvalidItt,
+    #[error("{0:?} is not an LPI")]
+    NotAnLpi(IntId),
+    #[error("Redistributor target doesn't match the type supported by the ITS")]
+    InvalidTarget,
+    #[error("ITS stopped processing commands due to a command error")]
+    CommandStalled,
+    #[error("{0:?} is not an SGI")]
+    NotAnSgi(IntId),
+    #[error("ITS doesn't support virtual LPIs")]
+    VirtualLpisNotSupported,
+    #[error("vPE tables are not correctly aligned or have an invalid number of ID bits")]
+    InvalidVirtualPe,
+    #[error("ITS doesn't support vSGIs")]
+    VsgisNotSupported,
+}
+
+/// Identifies a redistributor as the target of an ITS command.
+///
+/// Which variant must be used depends on [`GitsTyper::physical_target_addresses`]; see
+/// [`Its::redistributor_target`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RedistributorTarget {
+    /// The 64 KiB aligned physical address of the redistributor.
+    PhysicalAddress(u64),
+    /// The processor number of the redistributor, from [`GicrTyper::processor_number`].
+    ProcessorNumber(u16),
+}
+
+impl RedistributorTarget {
+    /// Returns the value of the RDbase field of a command.
+    fn rd_base(self) -> u64 {
+        match self {
+            Self::PhysicalAddress(address) => address & 0x000f_ffff_ffff_0000,
+            Self::ProcessorNumber(processor_number) => u64::from(processor_number) << 16,
+        }
+    }
+}
+
+/// The ITS collection mapped to a CPU core's redistributor.
+///
+/// This is used by [`GicV3`](super::GicV3) to invalidate cached LPI configuration through the ITS
+/// when the redistributors don't support doing so directly.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CpuCollection {
+    /// The ID of a collection which has been mapped to the redistributor with MAPC.
+    pub collection_id: u16,
+    /// The redistributor, as passed to MAPC.
+    pub target: RedistributorTarget,
+}
+
+/// The configuration of a GICv4.1 virtual SGI, set with [`Its::vsgi`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct VsgiConfig {
+    /// The priority of the vSGI. Only the top 4 bits are used.
+    pub priority: u8,
+    /// Whether the vSGI is in virtual group 1 rather than group 0.
+    pub group1: bool,
+    /// Whether the vSGI is enabled.
+    pub enabled: bool,
+}
+
+/// A command in the ITS command queue.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(C, align(32))]
+pub struct ItsCommand([u64; 4]);
+
+impl ItsCommand {
+    /// An empty command, which may be used to initialise memory for the command queue.
+    pub const EMPTY: Self = Self([0; 4]);
+
+    const MOVI: u64 = 0x01;
+    const SYNC: u64 = 0x05;
+    const MAPD: u64 = 0x08;
+    const MAPC: u64 = 0x09;
+    const MAPTI: u64 = 0x0a;
+    const MAPI: u64 = 0x0b;
+    const INV: u64 = 0x0c;
+    const INVALL: u64 = 0x0d;
+    const DISCARD: u64 = 0x0f;
+    const VMOVI: u64 = 0x21;
+    const VMOVP: u64 = 0x22;
+    const VSGI: u64 = 0x23;
+    const VSYNC: u64 = 0x25;
+    const VMAPP: u64 = 0x29;
+    const VMAPTI: u64 = 0x2a;
+    const VMAPI: u64 = 0x2b;
+    const VINVALL: u64 = 0x2d;
+
+    /// The value of the Dbell_pINTID field meaning that there is no doorbell LPI.
+    const NO_DOORBELL: u32 = 1023;
+    /// In the GICv4.1 VMAPP command, whether the vPE table entry is allocated or deallocated.
+    const VMAPP_ALLOC: u64 = 1 << 8;
+    /// In the GICv4.1 VMAPP command, whether the vPE's pending table is known to be zeroed.
+    const VMAPP_PTZ: u64 = 1 << 9;
+
+    const fn new(command_number: u64) -> Self {
+        Self([command_number, 0, 0, 0])
+    }
+
+    const fn device_id(mut self, device_id: u32) -> Self {
+        self.0[0] |= (device_id as u64) << 32;
+        self
+    }
+
+    const fn event_id(mut self, event_id: u32) -> Self {
+        self.0[1] |= event_id as u64;
+        self
+    }
+
+    const fn collection_id(mut self, collection_id: u16) -> Self {
+        self.0[2] |= collection_id as u64;
+        self
+    }
+
+    const fn valid(mut self, valid: bool) -> Self {
+        if valid {
+            self.0[2] |= 1 << 63;
+        }
+        self
+    }
+
+    fn target(mut self, target: RedistributorTarget) -> Self {
+        self.0[2] |= target.rd_base();
+        self
+    }
+
+    const fn vpe_id(mut self, vpe_id: u16) -> Self {
+        self.0[1] |= (vpe_id as u64) << 32;
+        self
+    }
+
+    const fn doorbell(mut self, doorbell: Option<IntId>) -> Self {
+        let doorbell = match doorbell {
+            Some(intid) => intid.0,
+            None => Self::NO_DOORBELL,
+        };
+        self.0[2] |= (doorbell as u64) << 32;
+        self
+    }
+
+    /// Returns the command number.
+    pub fn command_number(&self) -> u8 {
+        self.0[0] as u8
+    }
+
+    fn mapd(device_id: u32, itt: Option<(u64, u32)>) -> Self {
+        let command = Self::new(Self::MAPD).device_id(device_id);
+        if let Some((itt_address, event_id_bits)) = itt {
+            let mut command = command.valid(true);
+            command.0[1] |= u64::from(event_id_bits - 1) & 0b11111;
+            command.0[2] |= itt_address & 0x000f_ffff_ffff_ff00;
+            command
+        } else {
+            command
+        }
+    }
+
+    fn mapc(collection_id: u16, target: Option<RedistributorTarget>) -> Self {
+        let command = Self::new(Self::MAPC).collection_id(collection_id);
+        if let Some(target) = target {
+            command.valid(true).target(target)
+        } else {
+            command
+        }
+    }
+
+    fn mapti(device_id: u32, event_id: u32, intid: IntId, collection_id: u16) -> Self {
+        let mut command = Self::new(Self::MAPTI)
+            .device_id(device_id)
+            .event_id(event_id)
+            .collection_id(collection_id);
+        command.0[1] |= u64::from(intid.0) << 32;
+        command
+    }
+
+    fn mapi(device_id: u32, event_id: u32, collection_id: u16) -> Self {
+        Self::new(Self::MAPI)
+            .device_id(device_id)
+            .event_id(event_id)
+            .collection_id(collection_id)
+    }
+
+    fn movi(device_id: u32, event_id: u32, collection_id: u16) -> Self {
+        Self::new(Self::MOVI)
+            .device_id(device_id)
+            .event_id(event_id)
+            .collection_id(collection_id)
+    }
+
+    fn discard(device_id: u32, event_id: u32) -> Self {
+        Self::new(Self::DISCARD)
+            .device_id(device_id)
+            .event_id(event_id)
+    }
+
+    fn inv(device_id: u32, event_id: u32) -> Self {
+        Self::new(Self::INV).device_id(device_id).event_id(event_id)
+    }
+
+    fn invall(collection_id: u16) -> Self {
+        Self::new(Self::INVALL).collection_id(collection_id)
+    }
+
+    fn sync(target: RedistributorTarget) -> Self {
+        Self::new(Self::SYNC).target(target)
+    }
+
+    fn vmapp(vpe_id: u16, vpt: Option<(RedistributorTarget, u64, u32)>) -> Self {
+        let command = Self::new(Self::VMAPP).vpe_id(vpe_id);
+        if let Some((target, vpt_address, id_bits)) = vpt {
+            let mut command = command.valid(true).target(target);
+            command.0[3] |=
+                (vpt_address & 0x000f_ffff_ffff_0000) | (u64::from(id_bits - 1) & 0b11111);
+            command
+        } else {
+            command
+        }
+    }
+
+    /// Builds a VMAPP command in the GICv4.1 layout.
+    ///
+    /// Alloc is always set, so the vPE table entry is allocated when the vPE is mapped and
+    /// deallocated when it is unmapped.
+    fn gicv4_1_vmapp(vpe_id: u16, vpe: Option<(RedistributorTarget, &VirtualPe)>) -> Self {
+        let mut command = Self::new(Self::VMAPP).vpe_id(vpe_id);
+        command.0[0] |= Self::VMAPP_ALLOC;
+        if let Some((target, vpe)) = vpe {
+            command = command.valid(true).target(target);
+            command.0[0] |= vpe.config_table_physical_address & 0x000f_ffff_ffff_0000;
+            if vpe.pending_table_zeroed {
+                command.0[0] |= Self::VMAPP_PTZ;
+            }
+            command.0[1] |= u64::from(
+                vpe.default_doorbell
+                    .map_or(Self::NO_DOORBELL, |doorbell| doorbell.0),
+            );
+            command.0[3] |= (vpe.pending_table_physical_address & 0x000f_ffff_ffff_0000)
+                | (u64::from(vpe.id_bits - 1) & 0b11111);
+        }
+        command
+    }
+
+    fn vmapti(
+        device_id: u32,
+        event_id: u32,
+        vpe_id: u16,
+        vintid: IntId,
+        doorbell: Option<IntId>,
+    ) -> Self {
+        let mut command = Self::new(Self::VMAPTI)
+            .device_id(device_id)
+            .event_id(event_id)
+            .vpe_id(vpe_id)
+            .doorbell(doorbell);
+        command.0[2] |= u64::from(vintid.0);
+        command
+    }
+
+    fn vmapi(device_id: u32, event_id: u32, vpe_id: u16, doorbell: Option<IntId>) -> Self {
+        Self::new(Self::VMAPI)
+            .device_id(device_id)
+            .event_id(event_id)
+            .vpe_id(vpe_id)
+            .doorbell(doorbell)
+    }
+
+    fn vmovi(device_id: u32, event_id: u32, vpe_id: u16, doorbell: Option<IntId>) -> Self {
+        let mut command = Self::new(Self::VMOVI)
+            .device_id(device_id)
+            .event_id(event_id)
+            .vpe_id(vpe_id);
+        if let Some(doorbell) = doorbell {
+            // The D bit indicates that Dbell_pINTID is valid.
+            command = command.doorbell(Some(doorbell));
+            command.0[2] |= 1;
+        }
+        command
+    }
+
+    fn vmovp(
+        vpe_id: u16,
+        target: RedistributorTarget,
+        its_list: u16,
+        sequence_number: u16,
+    ) -> Self {
+        let mut command = Self::new(Self::VMOVP).vpe_id(vpe_id).target(target);
+        command.0[0] |= u64::from(sequence_number) << 32;
+        command.0[1] |= u64::from(its_list);
+        command
+    }
+
+    fn vsgi(vpe_id: u16, sgi: u32, config: VsgiConfig, pending: Option<bool>) -> Self {
+        let mut command = Self::new(Self::VSGI).vpe_id(vpe_id);
+        command.0[0] |= (u64::from(sgi & 0b1111) << 32) | (u64::from(config.priority >> 4) << 20);
+        if config.group1 {
+            command.0[0] |= 1 << 10;
+        }
+        if config.enabled {
+            command.0[0] |= 1 << 9;
+        }
+        match pending {
+            Some(true) => command.0[0] |= 1 << 8,
+            Some(false) => command.0[0] |= 1 << 19,
+            None => {}
+        }
+        command
+    }
+
+    fn vsync(vpe_id: u16) -> Self {
+        Self::new(Self::VSYNC).vpe_id(vpe_id)
+    }
+
+    fn vinvall(vpe_id: u16) -> Self {
+        Self::new(Self::VINVALL).vpe_id(vpe_id)
+    }
+}
+
+/// Driver for a GIC Interrupt Translation Service.
+#[derive(Debug)]
+pub struct Its<'a> {
+    gits: UniqueMmioPointer<'a, Gits>,
+    command_queue: &'a mut [ItsCommand],
+    command_queue_physical_address: u64,
+    /// The index in the command queue at which the next command will be written.
+    write_index: usize,
+}
+
+impl<'a> Its<'a> {
+    const CREADR_STALLED: u64 = 1 << 0;
+    const COMMAND_QUEUE_OFFSET_MASK: u64 = 0xf_ffe0;
+
+    /// Constructs a new instance of the driver for an ITS with the given control registers and
+    /// command queue.
+    ///
+    /// [`Its::setup`] must be called before any commands are sent.
+    ///
+    /// # Safety
+    ///
+    /// The given base address must point to the GIC ITS control registers. This region must be
+    /// mapped into the address space of the process as device memory, and not have any other
+    /// aliases, either via another instance of this driver or otherwise.
+    ///
+    /// `command_queue_physical_address` must be the physical address of `command_queue`, and
+    /// `command_queue` must be mapped with the attributes which will be passed to [`Its::setup`].
+    pub unsafe fn new(
+        gits: *mut Gits,
+        command_queue: &'a mut [ItsCommand],
+        command_queue_physical_address: u64,
+    ) -> Self {
+        Self {
+            // SAFETY: Our caller promised that `gits` is a valid and unique pointer to a GIC ITS.
+            gits: unsafe { UniqueMmioPointer::new(NonNull::new(gits).unwrap()) },
+            command_queue,
+            command_queue_physical_address,
+            write_index: 0,
+        }
+    }
+
+    /// Returns information about what the ITS implementation supports.
+    pub fn typer(&self) -> GitsTyper {
+        field_shared!(self.gits, typer).read()
+    }
+
+    /// Returns whether the ITS is enabled.
+    pub fn enabled(&self) -> bool {
+        field_shared!(self.gits, ctlr)
+            .read()
+            .contains(GitsCtlr::Enabled)
+    }
+
+    /// Configures the ITS to use the command queue, accessing it with the given memory attributes.
+    ///
+    /// The ITS must not be enabled.
+    pub fn setup(&mut self, attributes: MemoryAttributes) -> Result<(), ITSError> {
+        if self.enabled() {
+            return Err(ITSError::AlreadyEnabled);
+        }
+
+        let size = size_of_val(self.command_queue);
+        if size == 0
+            || !size.is_multiple_of(0x1000)
+            || size > 256 * 0x1000
+            || !self.command_queue_physical_address.is_multiple_of(0x1000)
+        {
+            return Err(ITSError::InvalidCommandQueue);
+        }
+
+        self.command_queue.fill(ItsCommand::EMPTY);
+        dsb_st();
+
+        let cbaser = (1 << 63)
+            | attributes.to_gits_bits()
+            | (self.command_queue_physical_address & 0x000f_ffff_ffff_f000)
+            | (size / 0x1000 - 1) as u64;
+        field!(self.gits, cbaser).write(cbaser);
+        if MemoryAttributes::from_gits_bits(field_shared!(self.gits, cbaser).read()) != attributes {
+            return Err(ITSError::UnsupportedAttributes);
+        }
+        field!(self.gits, cwriter).write(0);
+        self.write_index = 0;
+
+        Ok(())
+    }
+
+    /// Returns the index of the `GITS_BASER<n>` register for the table of the given type, if any.
+    fn table_index(&self, table_type: ItsTableType) -> Option<usize> {
+        let basers = field_shared!(self.gits, baser);
+        (0..8).find(|&i| basers.get(i).unwrap().read().table_type() == Some(table_type))
+    }
+
+    /// Returns the value of the `GITS_BASER<n>` register for the table of the given type, or `None`
+    /// if the ITS doesn't have such a table.
+    ///
+    /// On GICv4.1 the vPE table can then be shared with redistributors with
+    /// [`GicV3::set_vpe_table`](super::GicV3::set_vpe_table).
+    pub fn baser(&self, table_type: ItsTableType) -> Option<GitsBaser> {
+        let index = self.table_index(table_type)?;
+        Some(field_shared!(self.gits, baser).get(index).unwrap().read())
+    }
+
+    /// Returns the size in bytes of each entry of the table of the given type, or `None` if the ITS
+    /// doesn't have such a table.
+    pub fn table_entry_size(&self, table_type: ItsTableType) -> Option<usize> {
+        let index = self.table_index(table_type)?;
+        Some(
+            field_shared!(self.gits, baser)
+                .get(index)
+                .unwrap()
+                .read()
+                .entry_size(),
+        )
+    }
+
+    /// Configures the ITS to use the given memory for the flat table of the given type.
+    ///
+    /// The table is zeroed before use. It must be large enough to hold an entry of
+    /// [`Its::table_entry_size`] bytes for every ID which will be mapped. The ITS must not be
+    /// enabled.
+    ///
+    /// # Safety
+    ///
+    /// `physical_address` must be the physical address of `table`, and `table` must be mapped with
+    /// the given memory attributes.
+    pub unsafe fn set_table(
+        &mut self,
+        table_type: ItsTableType,
+        table: &'a mut [u8],
+        physical_address: u64,
+        page_size: ItsPageSize,
+        attributes: MemoryAttributes,
+    ) -> Result<(), ITSError> {
+        if self.enabled() {
+            return Err(ITSError::AlreadyEnabled);
+        }
+        let index = self
+            .table_index(table_type)
+            .ok_or(ITSError::NoSuchTable(table_type))?;
+
+        let page_bytes = page_size.bytes();
+        let pages = table.len() / page_bytes;
+        if pages == 0
+            || pages > 256
+            || !table.len().is_multiple_of(page_bytes)
+            || !physical_address.is_multiple_of(page_bytes as u64)
+        {
+            return Err(ITSError::InvalidTable);
+        }
+
+        table.fill(0);
+        dsb_st();
+
+        let mut basers = field!(self.gits, baser);
+        let mut baser = basers.get(index).unwrap();
+        let old_value = baser.read();
+        baser.write(old_value.with_table(physical_address, pages, page_size, attributes));
+
+        // Some fields may be read-only, so check that they took the value we wanted.
+        let new_value = baser.read();
+        let result = if new_value.page_size() != page_size {
+            Err(ITSError::UnsupportedPageSize)
+        } else if new_value.attributes() != attributes {
+            Err(ITSError::UnsupportedAttributes)
+        } else {
+            Ok(())
+        };
+        if result.is_err() {
+            baser.write(old_value);
+        }
+        result
+    }
+
+    /// Enables the ITS, so that it processes commands and translates interrupts.
+    pub fn enable(&mut self) {
+        let ctlr = field_shared!(self.gits, ctlr).read();
+        field!(self.gits, ctlr).write(ctlr | GitsCtlr::Enabled);
+    }
+
+    /// Disables the ITS, and blocks until it is quiescent.
+    pub fn disable(&mut self) {
+        let ctlr = field_shared!(self.gits, ctlr).read();
+        field!(self.gits, ctlr).write(ctlr - GitsCtlr::Enabled);
+        while !field_shared!(self.gits, ctlr)
+            .read()
+            .contains(GitsCtlr::Quiescent)
+        {
+            spin_loop();
+        }
+    }
+
+    /// Returns the ITS number, to include in the ITS list passed to [`Its::vmovp`].
+    pub fn its_number(&self) -> u8 {
+        field_shared!(self.gits, ctlr).read().its_number()
+    }
+
+    /// Returns the target to use in commands for the redistributor with the given `GICR_TYPER`
+    /// value and physical address, depending on which the ITS supports.
+    pub fn redistributor_target(
+        &self,
+        gicr_typer: GicrTyper,
+        gicr_physical_address: u64,
+    ) -> RedistributorTarget {
+        if self.typer().physical_target_addresses() {
+            RedistributorTarget::PhysicalAddress(gicr_physical_address)
+        } else {
+            RedistributorTarget::ProcessorNumber(gicr_typer.processor_number())
+        }
+    }
+
+    /// Returns the index of the next command which the ITS will read from the queue.
+    fn read_index(&self) -> Result<usize, ITSError> {
+        let creadr = field_shared!(self.gits, creadr).read();
+        if creadr & Self::CREADR_STALLED != 0 {
+            return Err(ITSError::CommandStalled);
+        }
+        Ok((creadr & Self::COMMAND_QUEUE_OFFSET_MASK) as usize / size_of::<ItsCommand>())
+    }
+
+    /// Adds the given command to the command queue, blocking until there is space if it is full.
+    fn queue_command(&mut self, command: ItsCommand) -> Result<(), ITSError> {
+        let next_index = (self.write_index + 1) % self.command_queue.len();
+        while self.read_index()? == next_index {
+            spin_loop();
+        }
+
+        self.command_queue[self.write_index] = command;
+        self.write_index = next_index;
+
+        // Make sure the command is visible to the ITS before telling it to read it.
+        dsb_st();
+        field!(self.gits, cwriter).write((next_index * size_of::<ItsCommand>()) as u64);
+        Ok(())
+    }
+
+    /// Sends the given command, and blocks until the ITS has read it.
+    fn send_command(&mut self, command: ItsCommand) -> Result<(), ITSError> {
+        self.queue_command(command)?;
+        while self.read_index()? != self.write_index {
+            spin_loop();
+        }
+        Ok(())
+    }
+
+    fn check_device_id(&self, device_id: u32) -> Result<(), ITSError> {
+        if u64::from(device_id) >> self.typer().device_id_bits() != 0 {
+            return Err(ITSError::DeviceIdOutOfRange(device_id));
+        }
+        Ok(())
+    }
+
+    fn check_event_id(&self, event_id: u32) -> Result<(), ITSError> {
+        if u64::from(event_id) >> self.typer().event_id_bits() != 0 {
+            return Err(ITSError::EventIdOutOfRange(event_id));
+        }
+        Ok(())
+    }
+
+    fn check_collection_id(&self, collection_id: u16) -> Result<(), ITSError> {
+        if u64::from(collection_id) >> self.typer().collection_id_bits() != 0 {
+            return Err(ITSError::CollectionIdOutOfRange(collection_id));
+        }
+        Ok(())
+    }
+
+    fn check_target(&self, target: RedistributorTarget) -> Result<(), ITSError> {
+        match target {
+            RedistributorTarget::PhysicalAddress(address)
+                if self.typer().physical_target_addresses() && address.is_multiple_of(0x10000) =>
+            {
+                Ok(())
+            }
+            RedistributorTarget::ProcessorNumber(_)
+                if !self.typer().physical_target_addresses() =>
+            {
+                Ok(())
+            }
+            _ => Err(ITSError::InvalidTarget),
+        }
+    }
+
+    fn check_virtual_lpis(&self) -> Result<(), ITSError> {
+        if !self.typer().virtual_lpis_supported() {
+            return Err(ITSError::VirtualLpisNotSupported);
+        }
+        Ok(())
+    }
+
+    fn check_doorbell(&self, doorbell: Option<IntId>) -> Result<(), ITSError> {
+        match doorbell {
+            Some(intid) if !intid.is_lpi() => Err(ITSError::NotAnLpi(intid)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Maps the given device ID to an interrupt translation table, with the given number of event
+    /// ID bits (MAPD).
+    ///
+    /// The ITT is zeroed before use. It must be 256 byte aligned and at least
+    /// [`GitsTyper::itt_entry_size`] bytes for each event ID.
+    ///
+    /// # Safety
+    ///
+    /// `itt_physical_address` must be the physical address of `itt`, and `itt` must be mapped with
+    /// the same memory attributes as the device table.
+    pub unsafe fn mapd(
+        &mut self,
+        device_id: u32,
+        event_id_bits: u32,
+        itt: &'a mut [u8],
+        itt_physical_address: u64,
+    ) -> Result<(), ITSError> {
+        self.check_device_id(device_id)?;
+        let typer = self.typer();
+        if event_id_bits == 0
+            || event_id_bits > typer.event_id_bits()
+            || (itt.len() as u64) < (typer.itt_entry_size() as u64) << event_id_bits
+            || !itt_physical_address.is_multiple_of(256)
+        {
+            return Err(ITSError::InvalidItt);
+        }
+
+        itt.fill(0);
+        dsb_st();
+        self.send_command(ItsCommand::mapd(
+            device_id,
+            Some((itt_physical_address, event_id_bits)),
+        ))
+    }
+
+    /// Unmaps the given device ID from its interrupt translation table (MAPD with V=0).
+    pub fn unmapd(&mut self, device_id: u32) -> Result<(), ITSError> {
+        self.check_device_id(device_id)?;
+        self.send_command(ItsCommand::mapd(device_id, None))
+    }
+
+    /// Maps the given collection ID to the given redistributor (MAPC).
+    pub fn mapc(
+        &mut self,
+        collection_id: u16,
+        target: RedistributorTarget,
+    ) -> Result<(), ITSError> {
+        self.check_collection_id(collection_id)?;
+        self.check_target(target)?;
+        self.send_command(ItsCommand::mapc(collection_id, Some(target)))
+    }
+
+    /// Unmaps the given collection ID (MAPC with V=0).
+    pub fn unmapc(&mut self, collection_id: u16) -> Result<(), ITSError> {
+        self.check_collection_id(collection_id)?;
+        self.send_command(ItsCommand::mapc(collection_id, None))
+    }
+
+    /// Maps the given event of the given device to the given LPI, in the given collection (MAPTI).
+    pub fn mapti(
+        &mut self,
+        device_id: u32,
+        event_id: u32,
+        intid: IntId,
+        collection_id: u16,
+    ) -> Result<(), ITSError> {
+        self.check_device_id(device_id)?;
+        self.check_event_id(event_id)?;
+        self.check_collection_id(collection_id)?;
+        if !intid.is_lpi() {
+            return Err(ITSError::NotAnLpi(intid));
+        }
+        self.send_command(ItsCommand::mapti(device_id, event_id, intid, collection_id))
+    }
+
+    /// Maps the given event of the given device to the LPI with the same ID as the event, in the
+    /// given collection (MAPI).
+    pub fn mapi(
+        &mut self,
+        device_id: u32,
+        event_id: u32,
+        collection_id: u16,
+    ) -> Result<(), ITSError> {
+        self.check_device_id(device_id)?;
+        self.check_event_id(event_id)?;
+        self.check_collection_id(collection_id)?;
+        if !IntId(event_id).is_lpi() {
+            return Err(ITSError::NotAnLpi(IntId(event_id)));
+        }
+        self.send_command(ItsCommand::mapi(device_id, event_id, collection_id))
+    }
+
+    /// Moves the LPI for the given event of the given device to the given collection (MOVI).
+    pub fn movi(
+        &mut self,
+        device_id: u32,
+        event_id: u32,
+        collection_id: u16,
+    ) -> Result<(), ITSError> {
+        self.check_device_id(device_id)?;
+        self.check_event_id(event_id)?;
+        self.check_collection_id(collection_id)?;
+        self.send_command(ItsCommand::movi(device_id, event_id, collection_id))
+    }
+
+    /// Removes the mapping for the given event of the given device, and clears the pending state of
+    /// the LPI it was mapped to (DISCARD).
+    pub fn discard(&mut self, device_id: u32, event_id: u32) -> Result<(), ITSError> {
+        self.check_device_id(device_id)?;
+        self.check_event_id(event_id)?;
+        self.send_command(ItsCommand::discard(device_id, event_id))
+    }
+
+    /// Makes the redistributor reload the configuration of the LPI which the given event of the
+    /// given device is mapped to (INV).
+    pub fn inv(&mut self, device_id: u32, event_id: u32) -> Result<(), ITSError> {
+        self.check_device_id(device_id)?;
+        self.check_event_id(event_id)?;
+        self.send_command(ItsCommand::inv(device_id, event_id))
+    }
+
+    /// Makes the redistributor which the given collection is mapped to reload the configuration of
+    /// all LPIs (INVALL).
+    pub fn invall(&mut self, collection_id: u16) -> Result<(), ITSError> {
+        self.check_collection_id(collection_id)?;
+        self.send_command(ItsCommand::invall(collection_id))
+    }
+
+    /// Blocks until the effects of all previous commands on the given redistributor are complete
+    /// (SYNC).
+    pub fn sync(&mut self, target: RedistributorTarget) -> Result<(), ITSError> {
+        self.check_target(target)?;
+        self.send_command(ItsCommand::sync(target))
+    }
+
+    /// Maps the given vPE ID to the given redistributor and the pending table of the given vPE
+    /// (VMAPP).
+    ///
+    /// The ITS must have a vPE table, set with [`Its::set_table`].
+    ///
+    /// On a GICv4.1 ITS the command also gives the vPE's configuration table and default doorbell
+    /// to the redistributors, and allocates the vPE table entry. This assumes that the vPE is only
+    /// mapped through this ITS; the vPE's configuration table must be 64 KiB aligned.
+    pub fn vmapp(
+        &mut self,
+        vpe_id: u16,
+        vpe: &VirtualPe,
+        target: RedistributorTarget,
+    ) -> Result<(), ITSError> {
+        self.check_virtual_lpis()?;
+        self.check_target(target)?;
+        if !vpe.pending_table_physical_address.is_multiple_of(0x10000)
+            || !(14..=32).contains(&vpe.id_bits)
+        {
+            return Err(ITSError::InvalidVirtualPe);
+        }
+        if self.typer().gicv4_1_vmapp() {
+            if !vpe.config_table_physical_address.is_multiple_of(0x10000) {
+                return Err(ITSError::InvalidVirtualPe);
+            }
+            self.send_command(ItsCommand::gicv4_1_vmapp(vpe_id, Some((target, vpe))))
+        } else {
+            self.send_command(ItsCommand::vmapp(
+                vpe_id,
+                Some((target, vpe.pending_table_physical_address, vpe.id_bits)),
+            ))
+        }
+    }
+
+    /// Unmaps the given vPE ID (VMAPP with V=0).
+    ///
+    /// On a GICv4.1 ITS this also deallocates the vPE table entry.
+    pub fn vunmapp(&mut self, vpe_id: u16) -> Result<(), ITSError> {
+        self.check_virtual_lpis()?;
+        if self.typer().gicv4_1_vmapp() {
+            self.send_command(ItsCommand::gicv4_1_vmapp(vpe_id, None))
+        } else {
+            self.send_command(ItsCommand::vmapp(vpe_id, None))
+        }
+    }
+
+    /// Maps the given event of the given device to the given virtual LPI of the given vPE (VMAPTI).
+    ///
+    /// If `doorbell` is given then that physical LPI is generated when the virtual LPI becomes
+    /// pending while the vPE is not resident.
+    pub fn vmapti(
+        &mut self,
+        device_id: u32,
+        event_id: u32,
+        vpe_id: u16,
+        vintid: IntId,
+        doorbell: Option<IntId>,
+    ) -> Result<(), ITSError> {
+        self.check_virtual_lpis()?;
+        self.check_device_id(device_id)?;
+        self.check_event_id(event_id)?;
+        if !vintid.is_lpi() {
+            return Err(ITSError::NotAnLpi(vintid));
+        }
+        self.check_doorbell(doorbell)?;
+        self.send_command(ItsCommand::vmapti(
+            device_id, event_id, vpe_id, vintid, doorbell,
+        ))
+    }
+
+    /// Maps the given event of the given device to the virtual LPI with the same ID as the event,
+    /// of the given vPE (VMAPI).
+    pub fn vmapi(
+        &mut self,
+        device_id: u32,
+        event_id: u32,
+        vpe_id: u16,
+        doorbell: Option<IntId>,
+    ) -> Result<(), ITSError> {
+        self.check_virtual_lpis()?;
+        self.check_device_id(device_id)?;
+        self.check_event_id(event_id)?;
+        if !IntId(event_id).is_lpi() {
+            return Err(ITSError::NotAnLpi(IntId(event_id)));
+        }
+        self.check_doorbell(doorbell)?;
+        self.send_command(ItsCommand::vmapi(device_id, event_id, vpe_id, doorbell))
+    }
+
+    /// Moves the virtual LPI for the given event of the given device to the given vPE, optionally
+    /// changing its doorbell LPI (VMOVI).
+    pub fn vmovi(
+        &mut self,
+        device_id: u32,
+        event_id: u32,
+        vpe_id: u16,
+        doorbell: Option<IntId>,
+    ) -> Result<(), ITSError> {
+        self.check_virtual_lpis()?;
+        self.check_device_id(device_id)?;
+        self.check_event_id(event_id)?;
+        self.check_doorbell(doorbell)?;
+        self.send_command(ItsCommand::vmovi(device_id, event_id, vpe_id, doorbell))
+    }
+
+    /// Moves the given vPE to the given redistributor (VMOVP).
+    ///
+    /// Unless [`GitsTyper::vmovp_single_its`] is true, this must be sent to every ITS which has the
+    /// vPE mapped, with `its_list` having a bit set for each of their [`Its::its_number`]s and the
+    /// same `sequence_number`. Otherwise `its_list` and `sequence_number` are ignored.
+    pub fn vmovp(
+        &mut self,
+        vpe_id: u16,
+        target: RedistributorTarget,
+        its_list: u16,
+        sequence_number: u16,
+    ) -> Result<(), ITSError> {
+        self.check_virtual_lpis()?;
+        self.check_target(target)?;
+        let (its_list, sequence_number) = if self.typer().vmovp_single_its() {
+            (0, 0)
+        } else {
+            (its_list, sequence_number)
+        };
+        self.send_command(ItsCommand::vmovp(vpe_id, target, its_list, sequence_number))
+    }
+
+    /// Sets the configuration of the given SGI of the given GICv4.1 vPE, and optionally sets or
+    /// clears its pending state (VSGI).
+    ///
+    /// The ITS must support vSGIs, as indicated by [`GitsTyper::vsgi_supported`], and the GIC must
+    /// support vSGIs without an active state; see [`GicV3::enable_nassgi`].
+    ///
+    /// [`GicV3::enable_nassgi`]: super::GicV3::enable_nassgi
+    pub fn vsgi(
+        &mut self,
+        vpe_id: u16,
+        intid: IntId,
+        config: VsgiConfig,
+        pending: Option<bool>,
+    ) -> Result<(), ITSError> {
+        self.check_virtual_lpis()?;
+        if !self.typer().vsgi_supported() {
+            return Err(ITSError::VsgisNotSupported);
+        }
+        if !intid.is_sgi() {
+            return Err(ITSError::NotAnSgi(intid));
+        }
+        self.send_command(ItsCommand::vsgi(vpe_id, intid.0, config, pending))
+    }
+
+    /// Blocks until the effects of all previous commands on the given vPE are complete (VSYNC).
+    pub fn vsync(&mut self, vpe_id: u16) -> Result<(), ITSError> {
+        self.check_virtual_lpis()?;
+        self.send_command(ItsCommand::vsync(vpe_id))
+    }
+
+    /// Makes the redistributor on which the given vPE is resident reload the configuration of all
+    /// of its virtual LPIs (VINVALL).
+    pub fn vinvall(&mut self, vpe_id: u16) -> Result<(), ITSError> {
+        self.check_virtual_lpis()?;
+        self.send_command(ItsCommand::vinvall(vpe_id))
+    }
+}
+
+// SAFETY: Any operations which change state require `&mut Its`, so `&Its` is fine to share.
+unsafe impl Sync for Its<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapd() {
+        let command = ItsCommand::mapd(0x1234, Some((0x8_1234_5600, 5)));
+        assert_eq!(command.command_number(), 0x08);
+        assert_eq!(
+            command.0,
+            [0x1234_0000_0008, 4, (1 << 63) | 0x8_1234_5600, 0]
+        );
+
+        let command = ItsCommand::mapd(0x1234, None);
+        assert_eq!(command.0, [0x1234_0000_0008, 0, 0, 0]);
+    }
+
+    #[test]
+    fn mapc() {
+        let command = ItsCommand::mapc(3, Some(RedistributorTarget::ProcessorNumber(2)));
+        assert_eq!(command.0, [0x09, 0, (1 << 63) | 0x2_0003, 0]);
+
+        let command = ItsCommand::mapc(3, Some(RedistributorTarget::PhysicalAddress(0x80a_0000)));
+        assert_eq!(command.0, [0x09, 0, (1 << 63) | 0x80a_0003, 0]);
+    }
+
+    #[test]
+    fn mapti() {
+        let command = ItsCommand::mapti(7, 42, IntId::lpi(5), 1);
+        assert_eq!(command.0, [0x7_0000_000a, (8197 << 32) | 42, 1, 0]);
+    }
+
+    #[test]
+    fn device_event_commands() {
+        assert_eq!(ItsCommand::mapi(7, 8192, 1).0, [0x7_0000_000b, 8192, 1, 0]);
+        assert_eq!(ItsCommand::movi(7, 42, 2).0, [0x7_0000_0001, 42, 2, 0]);
+        assert_eq!(ItsCommand::discard(7, 42).0, [0x7_0000_000f, 42, 0, 0]);
+        assert_eq!(ItsCommand::inv(7, 42).0, [0x7_0000_000c, 42, 0, 0]);
+    }
+
+    #[test]
+    fn collection_commands() {
+        assert_eq!(ItsCommand::invall(9).0, [0x0d, 0, 9, 0]);
+        assert_eq!(
+            ItsCommand::sync(RedistributorTarget::ProcessorNumber(1)).0,
+            [0x05, 0, 0x1_0000, 0]
+        );
+    }
+
+    #[test]
+    fn vmapp() {
+        let command = ItsCommand::vmapp(
+            5,
+            Some((RedistributorTarget::ProcessorNumber(2), 0x8_1234_0000, 16)),
+        );
+        assert_eq!(
+            command.0,
+            [0x29, 0x5_0000_0000, (1 << 63) | 0x2_0000, 0x8_1234_000f]
+        );
+
+        assert_eq!(ItsCommand::vmapp(5, None).0, [0x29, 0x5_0000_0000, 0, 0]);
+    }
+
+    #[test]
+    fn gicv4_1_vmapp() {
+        let mut vpe = VirtualPe::new(
+            0x8_5678_0000,
+            16,
+            0x8_1234_0000,
+            MemoryAttributes::INNER_SHAREABLE_WRITE_BACK,
+        );
+        let command =
+            ItsCommand::gicv4_1_vmapp(5, Some((RedistributorTarget::ProcessorNumber(2), &vpe)));
+        assert_eq!(
+            command.0,
+            [
+                0x8_5678_0329,
+                0x5_0000_03ff,
+                (1 << 63) | 0x2_0000,
+                0x8_1234_000f
+            ]
+        );
+
+        vpe.set_default_doorbell(Some(IntId::lpi(100)));
+        vpe.pending_table_zeroed = false;
+        let command =
+            ItsCommand::gicv4_1_vmapp(5, Some((RedistributorTarget::ProcessorNumber(2), &vpe)));
+        assert_eq!(command.0[0], 0x8_5678_0129);
+        assert_eq!(command.0[1], 0x5_0000_2064);
+
+        assert_eq!(
+            ItsCommand::gicv4_1_vmapp(5, None).0,
+            [0x129, 0x5_0000_0000, 0, 0]
+        );
+    }
+
+    #[test]
+    fn vmapti() {
+        let command = ItsCommand::vmapti(7, 42, 5, IntId::lpi(3), Some(IntId::lpi(100)));
+        assert_eq!(
+            command.0,
+            [0x7_0000_002a, 0x5_0000_002a, (8292 << 32) | 8195, 0]
+        );
+
+        let command = ItsCommand::vmapti(7, 42, 5, IntId::lpi(3), None);
+        assert_eq!(command.0[2], (1023 << 32) | 8195);
+    }
+
+    #[test]
+    fn vpe_commands() {
+        assert_eq!(
+            ItsCommand::vmapi(7, 8192, 5, None).0,
+            [0x7_0000_002b, 0x5_0000_2000, 1023 << 32, 0]
+        );
+        assert_eq!(
+            ItsCommand::vmovi(7, 42, 5, Some(IntId::lpi(1))).0,
+            [0x7_0000_0021, 0x5_0000_002a, (8193 << 32) | 1, 0]
+        );
+        assert_eq!(
+            ItsCommand::vmovi(7, 42, 5, None).0,
+            [0x7_0000_0021, 0x5_0000_002a, 0, 0]
+        );
+        assert_eq!(
+            ItsCommand::vmovp(5, RedistributorTarget::ProcessorNumber(3), 0b101, 9).0,
+            [0x9_0000_0022, 0x5_0000_0005, 0x3_0000, 0]
+        );
+        assert_eq!(ItsCommand::vsync(5).0, [0x25, 0x5_0000_0000, 0, 0]);
+        assert_eq!(
+            ItsCommand::vsgi(
+                5,
+                3,
+                VsgiConfig {
+                    priority: 0xa0,
+                    group1: true,
+                    enabled: true
+                },
+                Some(true)
+            )
+            .0,
+            [0x3_00a0_0723, 0x5_0000_0000, 0, 0]
+        );
+        assert_eq!(
+            ItsCommand::vsgi(5, 3, VsgiConfig::default(), Some(false)).0,
+            [0x3_0008_0023, 0x5_0000_0000, 0, 0]
+        );
+        assert_eq!(ItsCommand::vinvall(5).0, [0x2d, 0x5_0000_0000, 0, 0]);
+    }
+}
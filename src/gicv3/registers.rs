@@ -65,6 +65,58 @@ impl Debug for GicrCtlr {
     }
 }
 
+/// CPU interface control register (`ICC_CTLR_EL1`) value.
+///
+/// Only `PMHE`, `EOImode` and `CBPR` are writable; the other fields describe what the CPU interface
+/// supports.
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+pub struct IccCtlr(u32);
+
+bitflags! {
+    impl IccCtlr: u32 {
+        /// The CPU interface supports the extended SPI and PPI ranges.
+        const ExtRange = 1 << 19;
+        /// The CPU interface supports SGI target lists with a range selector.
+        const RSS = 1 << 18;
+        /// The CPU interface supports non-zero Aff3 values in SGI target affinities.
+        const A3V = 1 << 15;
+        /// The CPU interface supports generating local SEIs.
+        const SEIS = 1 << 14;
+        /// `ICC_PMR_EL1` is used as a hint for interrupt distribution.
+        const PMHE = 1 << 6;
+        /// `ICC_EOIR0_EL1` and `ICC_EOIR1_EL1` only drop priority, and `ICC_DIR_EL1` deactivates.
+        const EOImode = 1 << 1;
+        /// `ICC_BPR0_EL1` determines the preemption group for both group 0 and group 1.
+        const CBPR = 1 << 0;
+    }
+}
+
+impl IccCtlr {
+    /// Returns the number of priority bits implemented, from the PRIbits field.
+    pub fn priority_bits(self) -> u32 {
+        ((self.0 >> 8) & 0b111) + 1
+    }
+
+    /// Returns the number of interrupt ID bits supported, from the IDbits field.
+    pub fn id_bits(self) -> u32 {
+        if (self.0 >> 11) & 0b111 == 0b001 {
+            24
+        } else {
+            16
+        }
+    }
+}
+
+impl Debug for IccCtlr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "IccCtlr(")?;
+        bitflags::parser::to_writer(self, &mut *f)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
 /// This register controls the powerup sequence of the Redistributors.
 ///
 /// It is implemented only for GIC-600 and GIC-700
@@ -171,6 +223,21 @@ impl GicrTyper {
         (self.0 >> 8) as u16
     }
 
+    /// Returns the value of the CommonLPIAff field, which indicates which redistributors share an
+    /// LPI configuration table and, on GICv4.1, a vPE configuration table.
+    pub fn common_lpi_affinity(self) -> u32 {
+        ((self.0 >> 24) & 0b11) as u32
+    }
+
+    /// Returns whether the resident vPE is identified by its vPE ID, as in GICv4.1, rather than by
+    /// the address of its pending table.
+    ///
+    /// If this is true then `GICR_VPROPBASER` and `GICR_VPENDBASER` use their GICv4.1 layouts, and
+    /// the redistributor supports vSGIs.
+    pub fn rvpeid(self) -> bool {
+        self.0 & (1 << 7) != 0
+    }
+
     /// Returns whether MPAM is supported.
     pub fn mpam_supported(self) -> bool {
         self.0 & (1 << 6) != 0
@@ -223,6 +290,15 @@ impl Typer {
         IntId::espi(32 * self.espi_range() + 31)
     }
 
+    /// Returns the number of extended SPIs supported.
+    pub fn num_espis(self) -> u32 {
+        if self.espi_supported() {
+            32 * (self.espi_range() + 1)
+        } else {
+            0
+        }
+    }
+
     /// Returns the range of affinity level 0 values supported for targeted SGIs.
     pub fn range_selector_support(self) -> RangeSelectorSupport {
         if self.0 & (1 << 26) == 0 {
@@ -299,6 +375,29 @@ impl Typer {
     }
 }
 
+/// Interrupt controller type register 2 value.
+#[derive(Clone, Copy, Debug, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+#[repr(transparent)]
+pub struct Typer2(u32);
+
+impl Typer2 {
+    /// Returns whether SGIs can be configured to not have an active state, with
+    /// `GicdCtlr::nASSGIreq`.
+    pub fn nassgi_capable(self) -> bool {
+        self.0 & (1 << 8) != 0
+    }
+
+    /// Returns the number of vPE ID bits supported by the GICv4.1 ITSs and redistributors.
+    pub fn vpe_id_bits(self) -> u32 {
+        // If VIL is clear then 16 bits are supported, otherwise VID specifies the number.
+        if self.0 & (1 << 7) != 0 {
+            (self.0 & 0b11111) + 1
+        } else {
+            16
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum RangeSelectorSupport {
     /// The IRI supports targeted SGIs with affinity level 0 values up to 15.
@@ -317,23 +416,23 @@ pub struct Gicd {
     /// Distributor implementer identification register.
     pub iidr: u32,
     /// Interrupt controller type register 2.
-    pub typer2: u32,
+    pub typer2: ReadPure<Typer2>,
     /// Error reporting status register.
     pub statusr: u32,
     _reserved0: [u32; 3],
     /// Implementation defined registers.
     pub implementation_defined: [u32; 8],
     /// Set SPI register.
-    pub setspi_nsr: u32,
+    pub setspi_nsr: WriteOnly<u32>,
     _reserved1: u32,
     /// Clear SPI register.
-    pub clrspi_nsr: u32,
+    pub clrspi_nsr: WriteOnly<u32>,
     _reserved2: u32,
     /// Set SPI secure register.
-    pub setspi_sr: u32,
+    pub setspi_sr: WriteOnly<u32>,
     _reserved3: u32,
     /// Clear SPI secure register.
-    pub clrspi_sr: u32,
+    pub clrspi_sr: WriteOnly<u32>,
     _reserved4: [u32; 9],
     /// Interrupt group registers.
     pub igroupr: [ReadPureWrite<u32>; 32],
@@ -342,13 +441,13 @@ pub struct Gicd {
     /// Interrupt clear-enable registers.
     pub icenabler: [ReadPureWrite<u32>; 32],
     /// Interrupt set-pending registers.
-    pub ispendr: [u32; 32],
+    pub ispendr: [ReadPureWrite<u32>; 32],
     /// Interrupt clear-pending registers.
-    pub icpendr: [u32; 32],
+    pub icpendr: [ReadPureWrite<u32>; 32],
     /// Interrupt set-active registers.
-    pub isactiver: [u32; 32],
+    pub isactiver: [ReadPureWrite<u32>; 32],
     /// Interrupt clear-active registers.
-    pub icactiver: [u32; 32],
+    pub icactiver: [ReadPureWrite<u32>; 32],
     /// Interrupt priority registers.
     pub ipriorityr: [ReadPureWrite<u8>; 1024],
     /// Interrupt processor targets registers.
@@ -369,7 +468,7 @@ pub struct Gicd {
     pub spendsgir: [u32; 4],
     _reserved7: [u32; 20],
     /// Non-maskable interrupt registers.
-    pub inmir: [u32; 32],
+    pub inmir: [ReadPureWrite<u32>; 32],
     /// Interrupt group registers for extended SPI range.
     pub igroupr_e: [ReadPureWrite<u32>; 32],
     _reserved8: [u32; 96],
@@ -380,16 +479,16 @@ pub struct Gicd {
     pub icenabler_e: [ReadPureWrite<u32>; 32],
     _reserved10: [u32; 96],
     /// Interrupt set-pending registers for extended SPI range.
-    pub ispendr_e: [u32; 32],
+    pub ispendr_e: [ReadPureWrite<u32>; 32],
     _reserved11: [u32; 96],
     /// Interrupt clear-pending registers for extended SPI range.
-    pub icpendr_e: [u32; 32],
+    pub icpendr_e: [ReadPureWrite<u32>; 32],
     _reserved12: [u32; 96],
     /// Interrupt set-active registers for extended SPI range.
-    pub isactive_e: [u32; 32],
+    pub isactive_e: [ReadPureWrite<u32>; 32],
     _reserved13: [u32; 96],
     /// Interrupt clear-active registers for extended SPI range.
-    pub icactive_e: [u32; 32],
+    pub icactive_e: [ReadPureWrite<u32>; 32],
     _reserved14: [u32; 224],
     /// Interrupt priority registers for extended SPI range.
     pub ipriorityr_e: [ReadPureWrite<u8>; 1024],
@@ -404,13 +503,13 @@ pub struct Gicd {
     pub nsacr_e: [u32; 32],
     _reserved18: [u32; 288],
     /// Non-maskable interrupt registers for extended SPI range.
-    pub inmr_e: [u32; 32],
+    pub inmr_e: [ReadPureWrite<u32>; 32],
     _reserved19: [u32; 2400],
     /// Interrupt routing registers.
-    pub irouter: [u64; 988],
+    pub irouter: [ReadPureWrite<u64>; 988],
     _reserved20: [u32; 8],
     /// Interrupt routing registers for extended SPI range.
-    pub irouter_e: [u64; 1024],
+    pub irouter_e: [ReadPureWrite<u64>; 1024],
     _reserved21: [u32; 2048],
     /// Implementation defined registers.
     pub implementation_defined2: [u32; 4084],
@@ -518,24 +617,24 @@ pub struct Sgi {
     pub icenabler_e: [ReadPureWrite<u32>; 2],
     _reserved3: [u32; 29],
     /// Interrupt set-pending register 0.
-    pub ispendr0: u32,
+    pub ispendr0: ReadPureWrite<u32>,
     /// Interrupt set-pending registers for extended PPI range.
-    pub ispendr_e: [u32; 2],
+    pub ispendr_e: [ReadPureWrite<u32>; 2],
     _reserved4: [u32; 29],
     /// Interrupt clear-pending register 0.
-    pub icpendr0: u32,
+    pub icpendr0: ReadPureWrite<u32>,
     /// Interrupt clear-pending registers for extended PPI range.
-    pub icpendr_e: [u32; 2],
+    pub icpendr_e: [ReadPureWrite<u32>; 2],
     _reserved5: [u32; 29],
     /// Interrupt set-active register 0.
-    pub isactiver0: u32,
+    pub isactiver0: ReadPureWrite<u32>,
     /// Interrupt set-active registers for extended PPI range.
-    pub isactive_e: [u32; 2],
+    pub isactive_e: [ReadPureWrite<u32>; 2],
     _reserved6: [u32; 29],
     /// Interrupt clear-active register 0.
-    pub icactiver0: u32,
+    pub icactiver0: ReadPureWrite<u32>,
     /// Interrupt clear-active registers for extended PPI range.
-    pub icactive_e: [u32; 2],
+    pub icactive_e: [ReadPureWrite<u32>; 2],
     _reserved7: [u32; 29],
     /// Interrupt priority registers.
     pub ipriorityr: [ReadPureWrite<u8>; 32],
@@ -555,24 +654,500 @@ pub struct Sgi {
     pub nsacr: ReadPureWrite<u32>,
     _reserved11: [u32; 95],
     /// Non-maskable interrupt register for PPIs.
-    pub inmir0: u32,
-    /// Non-maskable interrupt register for extended PPIs.
-    pub inmir_e: [u32; 31],
-    _reserved12: [u32; 11264],
+    pub inmir0: ReadPureWrite<u32>,
+    /// Non-maskable interrupt registers for extended PPIs.
+    pub inmir_e: [ReadPureWrite<u32>; 2],
+    _reserved12: [u32; 11293],
     /// Implementation defined registers.
     pub implementation_defined: [u32; 4084],
     _reserved13: [u32; 12],
 }
 
+/// GICv4 redistributor virtual LPI registers.
+///
+/// This frame is only present on redistributors which support virtual LPIs, following the SGI
+/// frame.
+#[repr(C, align(8))]
+pub struct GicrVlpi {
+    _reserved0: [u32; 28],
+    /// Virtual redistributor properties base address register.
+    pub vpropbaser: ReadPureWrite<u64>,
+    /// Virtual redistributor LPI pending table base address register.
+    pub vpendbaser: ReadPureWrite<u64>,
+    /// Virtual SGI register (GICv4.1 only).
+    pub vsgir: ReadPureWrite<u32>,
+    _reserved1: u32,
+    /// Virtual SGI pending register (GICv4.1 only).
+    pub vsgipendr: ReadPure<u32>,
+    _reserved2: [u32; 16349],
+}
+
+/// The shareability attribute which the GIC uses when accessing a table or queue in memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Shareability {
+    /// Non-shareable.
+    NonShareable = 0b00,
+    /// Inner shareable.
+    InnerShareable = 0b01,
+    /// Outer shareable.
+    OuterShareable = 0b10,
+}
+
+impl Shareability {
+    /// Decodes a 2-bit shareability field.
+    fn from_bits(bits: u64) -> Self {
+        match bits & 0b11 {
+            0b01 => Self::InnerShareable,
+            0b10 => Self::OuterShareable,
+            _ => Self::NonShareable,
+        }
+    }
+}
+
+/// The cacheability attribute which the GIC uses when accessing a table or queue in memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Cacheability {
+    /// Device-nGnRnE for inner cacheability. When used for outer cacheability this means that it is
+    /// the same as the inner cacheability.
+    Device = 0b000,
+    /// Normal non-cacheable.
+    NonCacheable = 0b001,
+    /// Normal read-allocate, write-through.
+    ReadAllocateWriteThrough = 0b010,
+    /// Normal read-allocate, write-back.
+    ReadAllocateWriteBack = 0b011,
+    /// Normal write-allocate, write-through.
+    WriteAllocateWriteThrough = 0b100,
+    /// Normal write-allocate, write-back.
+    WriteAllocateWriteBack = 0b101,
+    /// Normal read-allocate, write-allocate, write-through.
+    ReadWriteAllocateWriteThrough = 0b110,
+    /// Normal read-allocate, write-allocate, write-back.
+    ReadWriteAllocateWriteBack = 0b111,
+}
+
+impl Cacheability {
+    /// Decodes a 3-bit cacheability field.
+    fn from_bits(bits: u64) -> Self {
+        match bits & 0b111 {
+            0b000 => Self::Device,
+            0b001 => Self::NonCacheable,
+            0b010 => Self::ReadAllocateWriteThrough,
+            0b011 => Self::ReadAllocateWriteBack,
+            0b100 => Self::WriteAllocateWriteThrough,
+            0b101 => Self::WriteAllocateWriteBack,
+            0b110 => Self::ReadWriteAllocateWriteThrough,
+            _ => Self::ReadWriteAllocateWriteBack,
+        }
+    }
+}
+
+/// The memory attributes which the GIC uses when accessing a table or queue in memory.
+///
+/// These must match the attributes with which the memory is mapped by the CPU.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MemoryAttributes {
+    /// The shareability of the memory.
+    pub shareability: Shareability,
+    /// The inner cacheability of the memory.
+    pub inner_cacheability: Cacheability,
+    /// The outer cacheability of the memory.
+    pub outer_cacheability: Cacheability,
+}
+
+impl MemoryAttributes {
+    /// Inner shareable normal memory with inner write-back cacheability, as used for normal memory
+    /// on most systems.
+    pub const INNER_SHAREABLE_WRITE_BACK: Self = Self {
+        shareability: Shareability::InnerShareable,
+        inner_cacheability: Cacheability::ReadWriteAllocateWriteBack,
+        outer_cacheability: Cacheability::Device,
+    };
+
+    /// Non-shareable normal non-cacheable memory, for GICs which can't snoop CPU caches.
+    pub const NON_SHAREABLE_NON_CACHEABLE: Self = Self {
+        shareability: Shareability::NonShareable,
+        inner_cacheability: Cacheability::NonCacheable,
+        outer_cacheability: Cacheability::Device,
+    };
+
+    /// Returns the attributes encoded in the layout used by `GITS_CBASER` and `GITS_BASER<n>`.
+    pub(crate) fn to_gits_bits(self) -> u64 {
+        ((self.inner_cacheability as u64) << 59)
+            | ((self.outer_cacheability as u64) << 53)
+            | ((self.shareability as u64) << 10)
+    }
+
+    /// Decodes the attributes from the layout used by `GITS_CBASER` and `GITS_BASER<n>`.
+    pub(crate) fn from_gits_bits(bits: u64) -> Self {
+        Self {
+            shareability: Shareability::from_bits(bits >> 10),
+            inner_cacheability: Cacheability::from_bits(bits >> 59),
+            outer_cacheability: Cacheability::from_bits(bits >> 53),
+        }
+    }
+
+    /// Returns the attributes encoded in the layout used by `GICR_PROPBASER` and `GICR_PENDBASER`.
+    pub(crate) fn to_gicr_bits(self) -> u64 {
+        ((self.outer_cacheability as u64) << 56)
+            | ((self.shareability as u64) << 10)
+            | ((self.inner_cacheability as u64) << 7)
+    }
+
+    /// Decodes the attributes from the layout used by `GICR_PROPBASER` and `GICR_PENDBASER`.
+    pub(crate) fn from_gicr_bits(bits: u64) -> Self {
+        Self {
+            shareability: Shareability::from_bits(bits >> 10),
+            inner_cacheability: Cacheability::from_bits(bits >> 7),
+            outer_cacheability: Cacheability::from_bits(bits >> 56),
+        }
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+pub struct GitsCtlr(u32);
+
+bitflags! {
+    impl GitsCtlr: u32 {
+        const Quiescent = 1 << 31;
+        const UMSIirq = 1 << 8;
+        const ImDe = 1 << 1;
+        const Enabled = 1 << 0;
+    }
+}
+
+impl GitsCtlr {
+    /// Returns the ITS number, used to identify the ITS in the ITS list of a GICv4.0 VMOVP command.
+    pub fn its_number(self) -> u8 {
+        ((self.bits() >> 4) & 0b1111) as u8
+    }
+}
+
+impl Debug for GitsCtlr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "GitsCtlr(")?;
+        bitflags::parser::to_writer(self, &mut *f)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+/// Interrupt translation service type register value.
+#[derive(Clone, Copy, Debug, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+#[repr(transparent)]
+pub struct GitsTyper(u64);
+
+impl GitsTyper {
+    /// Returns whether a VMOVP command need only be sent to a single ITS, rather than to every ITS
+    /// with an ITS list and sequence number.
+    pub fn vmovp_single_its(self) -> bool {
+        self.0 & (1 << 37) != 0
+    }
+
+    /// Returns whether the ITS uses the GICv4.1 layout of the VMAPP command.
+    pub fn gicv4_1_vmapp(self) -> bool {
+        self.0 & (1 << 40) != 0
+    }
+
+    /// Returns whether the ITS supports the GICv4.1 VSGI command.
+    pub fn vsgi_supported(self) -> bool {
+        self.0 & (1 << 39) != 0
+    }
+
+    /// Returns the number of collection ID bits supported.
+    pub fn collection_id_bits(self) -> u32 {
+        if self.0 & (1 << 36) != 0 {
+            (((self.0 >> 32) & 0b1111) + 1) as u32
+        } else {
+            16
+        }
+    }
+
+    /// Returns the number of collections which the ITS can hold without a collection table in
+    /// memory.
+    pub fn hardware_collection_count(self) -> u32 {
+        ((self.0 >> 24) & 0xff) as u32
+    }
+
+    /// Returns whether the target address for commands is the physical address of the
+    /// redistributor, rather than its processor number.
+    pub fn physical_target_addresses(self) -> bool {
+        self.0 & (1 << 19) != 0
+    }
+
+    /// Returns whether the ITS supports generating a local SError on command errors.
+    pub fn seis_supported(self) -> bool {
+        self.0 & (1 << 18) != 0
+    }
+
+    /// Returns the number of device ID bits supported.
+    pub fn device_id_bits(self) -> u32 {
+        (((self.0 >> 13) & 0b11111) + 1) as u32
+    }
+
+    /// Returns the number of event ID bits supported.
+    pub fn event_id_bits(self) -> u32 {
+        (((self.0 >> 8) & 0b11111) + 1) as u32
+    }
+
+    /// Returns the number of bytes per entry in an interrupt translation table.
+    pub fn itt_entry_size(self) -> usize {
+        (((self.0 >> 4) & 0b1111) + 1) as usize
+    }
+
+    /// Returns whether the number of collections held in memory is unlimited.
+    pub fn cumulative_collection_tables(self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+
+    /// Returns whether virtual LPIs and direct injection of virtual LPIs are supported.
+    pub fn virtual_lpis_supported(self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    /// Returns whether physical LPIs are supported.
+    pub fn physical_lpis_supported(self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+}
+
+/// The type of a table described by a `GITS_BASER<n>` register.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ItsTableType {
+    /// The device table.
+    Devices = 0b001,
+    /// The vPE table.
+    Vpes = 0b010,
+    /// The collection table.
+    Collections = 0b100,
+}
+
+/// The page size of a table described by a `GITS_BASER<n>` register.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ItsPageSize {
+    /// 4 KiB pages.
+    Size4KiB = 0b00,
+    /// 16 KiB pages.
+    Size16KiB = 0b01,
+    /// 64 KiB pages.
+    Size64KiB = 0b10,
+}
+
+impl ItsPageSize {
+    /// Returns the page size in bytes.
+    pub fn bytes(self) -> usize {
+        match self {
+            Self::Size4KiB => 4 << 10,
+            Self::Size16KiB => 16 << 10,
+            Self::Size64KiB => 64 << 10,
+        }
+    }
+}
+
+/// Interrupt translation service table base address register value.
+#[derive(Clone, Copy, Debug, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+#[repr(transparent)]
+pub struct GitsBaser(u64);
+
+impl GitsBaser {
+    const VALID: u64 = 1 << 63;
+    const INDIRECT: u64 = 1 << 62;
+    const TYPE_SHIFT: u64 = 56;
+    const ENTRY_SIZE_SHIFT: u64 = 48;
+    const PAGE_SIZE_SHIFT: u64 = 8;
+    const SIZE_MASK: u64 = 0xff;
+
+    /// Returns a value for a valid flat table with the given properties, keeping the read-only
+    /// fields from `self`.
+    ///
+    /// `physical_address` must be aligned to `page_size` and `pages` must be between 1 and 256.
+    pub(crate) fn with_table(
+        self,
+        physical_address: u64,
+        pages: usize,
+        page_size: ItsPageSize,
+        attributes: MemoryAttributes,
+    ) -> Self {
+        let read_only =
+            self.0 & ((0b111 << Self::TYPE_SHIFT) | (0b11111 << Self::ENTRY_SIZE_SHIFT));
+        let address = if page_size == ItsPageSize::Size64KiB {
+            // Bits [51:48] of the address are stored in bits [15:12] of the register.
+            (physical_address & 0x0000_ffff_ffff_0000) | ((physical_address >> 36) & 0xf000)
+        } else {
+            physical_address & 0x0000_ffff_ffff_f000
+        };
+        Self(
+            Self::VALID
+                | read_only
+                | attributes.to_gits_bits()
+                | address
+                | ((page_size as u64) << Self::PAGE_SIZE_SHIFT)
+                | ((pages - 1) as u64 & Self::SIZE_MASK),
+        )
+    }
+
+    /// Returns whether the table has been marked as valid.
+    pub fn valid(self) -> bool {
+        self.0 & Self::VALID != 0
+    }
+
+    /// Returns whether the table is a two-level table.
+    pub fn indirect(self) -> bool {
+        self.0 & Self::INDIRECT != 0
+    }
+
+    /// Returns the type of the table, or `None` if the register is not implemented.
+    pub fn table_type(self) -> Option<ItsTableType> {
+        match (self.0 >> Self::TYPE_SHIFT) & 0b111 {
+            0b001 => Some(ItsTableType::Devices),
+            0b010 => Some(ItsTableType::Vpes),
+            0b100 => Some(ItsTableType::Collections),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of bytes per table entry.
+    pub fn entry_size(self) -> usize {
+        (((self.0 >> Self::ENTRY_SIZE_SHIFT) & 0b11111) + 1) as usize
+    }
+
+    /// Returns the page size of the table.
+    pub fn page_size(self) -> ItsPageSize {
+        match (self.0 >> Self::PAGE_SIZE_SHIFT) & 0b11 {
+            0b00 => ItsPageSize::Size4KiB,
+            0b01 => ItsPageSize::Size16KiB,
+            _ => ItsPageSize::Size64KiB,
+        }
+    }
+
+    /// Returns the number of pages allocated to the table.
+    pub fn pages(self) -> usize {
+        ((self.0 & Self::SIZE_MASK) + 1) as usize
+    }
+
+    /// Returns the physical address of the table.
+    pub fn physical_address(self) -> u64 {
+        if self.page_size() == ItsPageSize::Size64KiB {
+            (self.0 & 0x0000_ffff_ffff_0000) | ((self.0 & 0xf000) << 36)
+        } else {
+            self.0 & 0x0000_ffff_ffff_f000
+        }
+    }
+
+    /// Returns the value of a GICv4.1 `GICR_VPROPBASER` which describes the same vPE
+    /// configuration table as this vPE table, to share it with a redistributor.
+    ///
+    /// Returns `None` if this is not a valid vPE table, or its size can't be described by
+    /// `GICR_VPROPBASER`.
+    pub(crate) fn to_gicv4_1_vpropbaser(self) -> Option<u64> {
+        if !self.valid()
+            || self.table_type() != Some(ItsTableType::Vpes)
+            || self.pages() > 128
+            || !self.entry_size().is_multiple_of(8)
+            || self.entry_size() > 64
+        {
+            return None;
+        }
+        let mut vpropbaser = 1 << 63
+            // Entry_Size is in units of 64 bits.
+            | ((self.entry_size() / 8 - 1) as u64) << 59
+            | ((self.page_size() as u64) << 53)
+            | self.attributes().to_gicr_bits()
+            | (self.physical_address() & 0x000f_ffff_ffff_f000)
+            | (self.pages() - 1) as u64;
+        if self.indirect() {
+            vpropbaser |= 1 << 55;
+        }
+        Some(vpropbaser)
+    }
+
+    /// Returns the memory attributes with which the ITS accesses the table.
+    pub fn attributes(self) -> MemoryAttributes {
+        MemoryAttributes::from_gits_bits(self.0)
+    }
+}
+
+/// GIC Interrupt Translation Service control registers.
+#[repr(C, align(8))]
+pub struct Gits {
+    /// ITS control register.
+    pub ctlr: ReadPureWrite<GitsCtlr>,
+    /// ITS implementer identification register.
+    pub iidr: ReadPure<u32>,
+    /// ITS type register.
+    pub typer: ReadPure<GitsTyper>,
+    /// Report maximum PARTID and PMG register.
+    pub mpamidr: ReadPure<u32>,
+    /// Set PARTID and PMG register.
+    pub partidr: ReadPureWrite<u32>,
+    /// ITS affinity register.
+    pub mpidr: ReadPure<u32>,
+    _reserved0: [u32; 25],
+    /// ITS command queue descriptor.
+    pub cbaser: ReadPureWrite<u64>,
+    /// ITS write register.
+    pub cwriter: ReadPureWrite<u64>,
+    /// ITS read register.
+    pub creadr: ReadPure<u64>,
+    _reserved1: [u32; 26],
+    /// ITS translation table descriptors.
+    pub baser: [ReadPureWrite<GitsBaser>; 8],
+    _reserved2: [u32; 12208],
+    /// Implementation defined registers.
+    pub implementation_defined: [u32; 4084],
+    /// ID registers.
+    pub id_registers: [ReadPure<u32>; 12],
+}
+
+/// GIC Interrupt Translation Service translation register frame.
+#[repr(C, align(8))]
+pub struct GitsTranslation {
+    _reserved0: [u32; 16],
+    /// ITS translation register, written by peripherals to generate an LPI.
+    pub translater: WriteOnly<u32>,
+    _reserved1: [u32; 16367],
+}
+
+/// GIC Interrupt Translation Service control and translation registers.
+#[repr(C, align(8))]
+pub struct GitsFrames {
+    /// ITS control register frame.
+    pub gits: Gits,
+    /// ITS translation register frame.
+    pub translation: GitsTranslation,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use core::mem::offset_of;
+
+    #[test]
+    fn icc_ctlr() {
+        let ctlr = IccCtlr::from_bits_retain(0x000c_8e02);
+        assert_eq!(ctlr.priority_bits(), 7);
+        assert_eq!(ctlr.id_bits(), 24);
+        assert!(ctlr.contains(IccCtlr::ExtRange | IccCtlr::RSS | IccCtlr::A3V | IccCtlr::EOImode));
+        assert!(!ctlr.intersects(IccCtlr::SEIS | IccCtlr::PMHE | IccCtlr::CBPR));
+        assert_eq!(IccCtlr::from_bits_retain(0x400).priority_bits(), 5);
+        assert_eq!(IccCtlr::from_bits_retain(0x400).id_bits(), 16);
+    }
 
     #[test]
     fn max_espi() {
         assert_eq!(Typer(0xffffffff).max_espi().0, IntId::ESPI_END - 1);
     }
 
+    #[test]
+    fn num_espis() {
+        assert_eq!(Typer(0).num_espis(), 0);
+        // ESPI_range is set but ESPI isn't.
+        assert_eq!(Typer(0x0800_0000).num_espis(), 0);
+        assert_eq!(Typer(0x0800_0100).num_espis(), 64);
+        assert_eq!(Typer(0xffffffff).num_espis(), IntId::MAX_ESPI_COUNT);
+    }
+
     #[test]
     fn num_lpis() {
         // num_LPIs is 0, no IDbits means no LPIs.
@@ -590,15 +1165,38 @@ mod tests {
         assert_eq!(Typer(16 << 11).num_lpis(), 1 << 17);
     }
 
+    #[test]
+    fn typer2() {
+        assert!(!Typer2(0).nassgi_capable());
+        assert!(Typer2(1 << 8).nassgi_capable());
+        assert_eq!(Typer2(0).vpe_id_bits(), 16);
+        assert_eq!(Typer2(0x0b).vpe_id_bits(), 16);
+        assert_eq!(Typer2(0x8b).vpe_id_bits(), 12);
+    }
+
     #[test]
     fn gicr_size() {
         // The size of the Gicr struct should match the offset from `RD_base` to `SGI_base`.
         assert_eq!(size_of::<Gicr>(), 0x10000);
     }
 
+    #[test]
+    fn sgi_layout() {
+        assert_eq!(offset_of!(Sgi, inmir0), 0xf80);
+        assert_eq!(offset_of!(Sgi, inmir_e), 0xf84);
+        assert_eq!(size_of::<Sgi>(), 0x10000);
+    }
+
+    #[test]
+    fn gicr_vlpi_size() {
+        // The size of the GicrVlpi struct should match the offset from `VLPI_base` to the
+        // following reserved frame.
+        assert_eq!(size_of::<GicrVlpi>(), 0x10000);
+    }
+
     #[test]
     fn gicr_typer_affinity() {
-        let gicr_typer = GicrTyper(0x12_34_56_78_c0ffeeee);
+        let gicr_typer = GicrTyper(0x1234_5678_c0ff_eeee);
 
         // Level 0 is 0x78, Level 1 is 0x56, etc.
         let expected_affinity_values = [0x78, 0x56, 0x34, 0x12];
@@ -631,4 +1229,119 @@ mod tests {
         assert!(!GicrTyper(0b0000000).disable_processor_group_supported());
         assert!(GicrTyper(0b0100000).disable_processor_group_supported());
     }
+
+    #[test]
+    fn gits_size() {
+        // Each ITS register frame is 64 KiB.
+        assert_eq!(size_of::<Gits>(), 0x10000);
+        assert_eq!(size_of::<GitsTranslation>(), 0x10000);
+    }
+
+    #[test]
+    fn gits_typer() {
+        let typer = GitsTyper(0x0000_0000_0008_83f1);
+        assert!(typer.physical_lpis_supported());
+        assert!(!typer.virtual_lpis_supported());
+        assert_eq!(typer.itt_entry_size(), 16);
+        assert_eq!(typer.event_id_bits(), 4);
+        assert_eq!(typer.device_id_bits(), 5);
+        assert!(typer.physical_target_addresses());
+        // CIL is 0, so there are 16 collection ID bits.
+        assert_eq!(typer.collection_id_bits(), 16);
+        assert_eq!(GitsTyper(0x17 << 32).collection_id_bits(), 8);
+        assert!(!typer.vmovp_single_its());
+        assert!(GitsTyper((1 << 37) | 0b10).vmovp_single_its());
+        assert!(GitsTyper((1 << 37) | 0b10).virtual_lpis_supported());
+        assert!(!typer.gicv4_1_vmapp());
+        assert!(GitsTyper((1 << 40) | (1 << 39)).gicv4_1_vmapp());
+        assert!(GitsTyper((1 << 40) | (1 << 39)).vsgi_supported());
+    }
+
+    #[test]
+    fn gits_baser() {
+        // Device table with 8 byte entries, as reset.
+        let baser = GitsBaser((0b001 << 56) | (7 << 48));
+        assert_eq!(baser.table_type(), Some(ItsTableType::Devices));
+        assert_eq!(baser.entry_size(), 8);
+        assert!(!baser.valid());
+
+        let baser = baser.with_table(
+            0x8_1234_0000,
+            4,
+            ItsPageSize::Size64KiB,
+            MemoryAttributes::INNER_SHAREABLE_WRITE_BACK,
+        );
+        assert!(baser.valid());
+        assert!(!baser.indirect());
+        assert_eq!(baser.table_type(), Some(ItsTableType::Devices));
+        assert_eq!(baser.entry_size(), 8);
+        assert_eq!(baser.page_size(), ItsPageSize::Size64KiB);
+        assert_eq!(baser.pages(), 4);
+        assert_eq!(
+            baser.attributes(),
+            MemoryAttributes::INNER_SHAREABLE_WRITE_BACK
+        );
+        assert_eq!(baser.0 & 0x0000_ffff_ffff_f000, 0x8_1234_0000);
+
+        // Bits [51:48] of the address move to bits [15:12] for 64 KiB pages.
+        let baser = GitsBaser(0).with_table(
+            0x000f_0000_0001_0000,
+            1,
+            ItsPageSize::Size64KiB,
+            MemoryAttributes::NON_SHAREABLE_NON_CACHEABLE,
+        );
+        assert_eq!(baser.0 & 0x0000_ffff_ffff_f000, 0x1_f000);
+        assert_eq!(baser.physical_address(), 0x000f_0000_0001_0000);
+        assert_eq!(baser.table_type(), None);
+    }
+
+    #[test]
+    fn gicv4_1_vpropbaser() {
+        // vPE table with 32 byte entries.
+        let baser = GitsBaser((0b010 << 56) | (31 << 48)).with_table(
+            0x8_1234_0000,
+            4,
+            ItsPageSize::Size16KiB,
+            MemoryAttributes::INNER_SHAREABLE_WRITE_BACK,
+        );
+        assert_eq!(
+            baser.to_gicv4_1_vpropbaser(),
+            Some(
+                (1 << 63)
+                    | (3 << 59)
+                    | (0b01 << 53)
+                    | MemoryAttributes::INNER_SHAREABLE_WRITE_BACK.to_gicr_bits()
+                    | 0x8_1234_0000
+                    | 3
+            )
+        );
+
+        // Not a vPE table.
+        let baser = GitsBaser((0b001 << 56) | (31 << 48)).with_table(
+            0x8_1234_0000,
+            4,
+            ItsPageSize::Size16KiB,
+            MemoryAttributes::INNER_SHAREABLE_WRITE_BACK,
+        );
+        assert_eq!(baser.to_gicv4_1_vpropbaser(), None);
+    }
+
+    #[test]
+    fn gicr_typer_gicv4_1() {
+        assert!(!GicrTyper(0).rvpeid());
+        assert!(GicrTyper(1 << 7).rvpeid());
+        assert_eq!(GicrTyper(0b10 << 24).common_lpi_affinity(), 2);
+    }
+
+    #[test]
+    fn gicr_memory_attributes() {
+        let attributes = MemoryAttributes {
+            shareability: Shareability::OuterShareable,
+            inner_cacheability: Cacheability::ReadAllocateWriteBack,
+            outer_cacheability: Cacheability::NonCacheable,
+        };
+        let bits = attributes.to_gicr_bits();
+        assert_eq!(bits, (0b001 << 56) | (0b10 << 10) | (0b011 << 7));
+        assert_eq!(MemoryAttributes::from_gicr_bits(bits), attributes);
+    }
 }
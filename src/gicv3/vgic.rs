@@ -0,0 +1,690 @@
+// Copyright 2026 The arm-gic Authors.
+// This project is dual-licensed under Apache 2.0 and MIT terms.
+// See LICENSE-APACHE and LICENSE-MIT for details.
+
+//! Software model of a virtual GICv3 distributor and redistributors, for a hypervisor to emulate
+//! guest accesses to them.
+//!
+//! A hypervisor traps guest accesses to the GICD and GICR frames and forwards them to
+//! [`VirtualGic::gicd_read`], [`VirtualGic::gicd_write`], [`VirtualGic::gicr_read`] and
+//! [`VirtualGic::gicr_write`], using offsets within the layouts of [`Gicd`] and [`GicrSgi`]. The
+//! model keeps the state of each SGI, PPI and SPI, and [`VirtualGic::next_injection`] decides which
+//! interrupt should next be injected into each vCPU, such as through the list registers.
+//!
+//! The model supports a single security state with affinity routing always enabled. LPIs and
+//! extended PPIs and SPIs are not supported.
+
+use super::registers::{Gicd, Gicr, GicrSgi, Sgi};
+use crate::{IntId, Trigger};
+use core::mem::offset_of;
+use thiserror::Error;
+
+/// An error which may be returned from emulating a register access.
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VgicError {
+    #[error("Unsupported {width} byte access at offset {offset:#x}")]
+    UnsupportedAccess { offset: usize, width: usize },
+}
+
+/// The state of a virtual interrupt.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InterruptState {
+    /// Whether the interrupt is enabled.
+    pub enabled: bool,
+    /// Whether the interrupt is pending, and not yet injected.
+    pub pending: bool,
+    /// Whether the interrupt is active, and not in a list register.
+    pub active: bool,
+    /// The priority of the interrupt.
+    pub priority: u8,
+    /// Whether the interrupt is in group 1 rather than group 0.
+    pub group1: bool,
+    /// The trigger configuration of the interrupt.
+    pub trigger: Trigger,
+    /// The vCPU into which the interrupt has been injected, if it hasn't yet been completed.
+    pub injected: Option<usize>,
+}
+
+impl InterruptState {
+    const RESET: Self = Self {
+        enabled: false,
+        pending: false,
+        active: false,
+        priority: 0,
+        group1: false,
+        trigger: Trigger::Level,
+        injected: None,
+    };
+
+    const SGI_RESET: Self = Self {
+        trigger: Trigger::Edge,
+        ..Self::RESET
+    };
+}
+
+/// A decision to inject a virtual interrupt into a vCPU.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Injection {
+    /// The virtual interrupt ID.
+    pub intid: IntId,
+    /// The priority of the virtual interrupt.
+    pub priority: u8,
+    /// Whether the virtual interrupt is in group 1 rather than group 0.
+    pub group1: bool,
+}
+
+/// The emulated state of a redistributor.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct VirtualRedistributor {
+    /// The MPIDR of the vCPU which the redistributor is connected to.
+    mpidr: u64,
+    /// The value of `GICR_WAKER`.
+    waker: u32,
+    /// The state of the SGIs and PPIs.
+    private: [InterruptState; 32],
+}
+
+/// A set of registers with one or two bits for each interrupt.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BitmapRegister {
+    Group,
+    SetEnable,
+    ClearEnable,
+    SetPending,
+    ClearPending,
+    SetActive,
+    ClearActive,
+    Config,
+}
+
+impl BitmapRegister {
+    /// Returns the number of interrupts covered by each 32-bit register.
+    fn interrupts_per_register(self) -> u32 {
+        if self == Self::Config { 16 } else { 32 }
+    }
+}
+
+const GICD_CTLR: usize = offset_of!(Gicd, ctlr);
+const GICD_TYPER: usize = offset_of!(Gicd, typer);
+const GICD_IIDR: usize = offset_of!(Gicd, iidr);
+const GICD_IPRIORITYR: usize = offset_of!(Gicd, ipriorityr);
+const GICD_IPRIORITYR_END: usize = GICD_IPRIORITYR + 1024;
+const GICD_IROUTER: usize = offset_of!(Gicd, irouter);
+const GICD_IROUTER_END: usize = GICD_IROUTER + IntId::MAX_SPI_COUNT as usize * 8;
+/// `GICD_PIDR2` is the seventh of the ID registers.
+const GICD_PIDR2: usize = offset_of!(Gicd, id_registers) + 6 * 4;
+
+/// The offsets of the bitmap registers in the distributor frame, and how many of each there are.
+const GICD_BITMAPS: [(usize, usize, BitmapRegister); 8] = [
+    (offset_of!(Gicd, igroupr), 32, BitmapRegister::Group),
+    (offset_of!(Gicd, isenabler), 32, BitmapRegister::SetEnable),
+    (offset_of!(Gicd, icenabler), 32, BitmapRegister::ClearEnable),
+    (offset_of!(Gicd, ispendr), 32, BitmapRegister::SetPending),
+    (offset_of!(Gicd, icpendr), 32, BitmapRegister::ClearPending),
+    (offset_of!(Gicd, isactiver), 32, BitmapRegister::SetActive),
+    (offset_of!(Gicd, icactiver), 32, BitmapRegister::ClearActive),
+    (offset_of!(Gicd, icfgr), 64, BitmapRegister::Config),
+];
+
+/// The offset of the SGI and PPI frame from the start of the redistributor.
+const SGI_FRAME: usize = offset_of!(GicrSgi, sgi);
+
+const GICR_IIDR: usize = offset_of!(Gicr, iidr);
+const GICR_TYPER: usize = offset_of!(Gicr, typer);
+const GICR_TYPER_END: usize = GICR_TYPER + 8;
+const GICR_WAKER: usize = offset_of!(Gicr, waker);
+/// `GICR_PIDR2` is the seventh of the ID registers.
+const GICR_PIDR2: usize = offset_of!(Gicr, id_registers) + 6 * 4;
+const GICR_IPRIORITYR: usize = SGI_FRAME + offset_of!(Sgi, ipriorityr);
+const GICR_IPRIORITYR_END: usize = GICR_IPRIORITYR + 32;
+
+/// The offsets of the bitmap registers in the redistributor frames, and how many of each there
+/// are.
+const GICR_BITMAPS: [(usize, usize, BitmapRegister); 8] = [
+    (
+        SGI_FRAME + offset_of!(Sgi, igroupr0),
+        1,
+        BitmapRegister::Group,
+    ),
+    (
+        SGI_FRAME + offset_of!(Sgi, isenabler0),
+        1,
+        BitmapRegister::SetEnable,
+    ),
+    (
+        SGI_FRAME + offset_of!(Sgi, icenabler0),
+        1,
+        BitmapRegister::ClearEnable,
+    ),
+    (
+        SGI_FRAME + offset_of!(Sgi, ispendr0),
+        1,
+        BitmapRegister::SetPending,
+    ),
+    (
+        SGI_FRAME + offset_of!(Sgi, icpendr0),
+        1,
+        BitmapRegister::ClearPending,
+    ),
+    (
+        SGI_FRAME + offset_of!(Sgi, isactiver0),
+        1,
+        BitmapRegister::SetActive,
+    ),
+    (
+        SGI_FRAME + offset_of!(Sgi, icactiver0),
+        1,
+        BitmapRegister::ClearActive,
+    ),
+    (
+        SGI_FRAME + offset_of!(Sgi, icfgr),
+        2,
+        BitmapRegister::Config,
+    ),
+];
+
+/// Finds the bitmap register at the given offset, and returns it along with the first interrupt ID
+/// which it covers.
+fn find_bitmap(
+    bitmaps: &[(usize, usize, BitmapRegister)],
+    offset: usize,
+) -> Option<(BitmapRegister, u32)> {
+    bitmaps.iter().find_map(|&(base, count, register)| {
+        if (base..base + count * 4).contains(&offset) {
+            let index = ((offset - base) / 4) as u32;
+            Some((register, index * register.interrupts_per_register()))
+        } else {
+            None
+        }
+    })
+}
+
+/// A software model of a virtual GICv3 distributor with `SPIS` SPIs, and a redistributor for each
+/// of `VCPUS` vCPUs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VirtualGic<const VCPUS: usize, const SPIS: usize> {
+    /// The value of `GICD_CTLR`.
+    ctlr: u32,
+    /// The state of the SPIs.
+    spis: [InterruptState; SPIS],
+    /// The value of `GICD_IROUTER<n>` for each SPI.
+    routes: [u64; SPIS],
+    redistributors: [VirtualRedistributor; VCPUS],
+}
+
+impl<const VCPUS: usize, const SPIS: usize> VirtualGic<VCPUS, SPIS> {
+    const CTLR_ENABLE_GRP0: u32 = 1 << 0;
+    const CTLR_ENABLE_GRP1: u32 = 1 << 1;
+    const CTLR_ARE: u32 = 1 << 4;
+    const CTLR_DS: u32 = 1 << 6;
+    /// The implementer ID reported in `GICD_IIDR` and `GICR_IIDR`, for Arm.
+    const IIDR: u32 = 0x43b;
+    /// The value of `GICD_PIDR2` and `GICR_PIDR2`, for GICv3.
+    const PIDR2: u32 = 0x3b;
+    const GICR_TYPER_LAST: u64 = 1 << 4;
+    const WAKER_PROCESSOR_SLEEP: u32 = 1 << 1;
+    const WAKER_CHILDREN_ASLEEP: u32 = 1 << 2;
+    const IROUTER_IRM: u64 = 1 << 31;
+    const AFFINITY_MASK: u64 = 0xff_00ff_ffff;
+    /// Only 5 bits of priority are implemented, the minimum which the virtual CPU interface
+    /// supports.
+    const PRIORITY_MASK: u8 = 0xf8;
+
+    /// Creates a new virtual GIC in its reset state, with redistributors for vCPUs with the given
+    /// MPIDR values.
+    pub fn new(mpidrs: [u64; VCPUS]) -> Self {
+        assert!(SPIS <= IntId::MAX_SPI_COUNT as usize);
+        let mut private = [InterruptState::RESET; 32];
+        private[..IntId::SGI_COUNT as usize].fill(InterruptState::SGI_RESET);
+        Self {
+            ctlr: 0,
+            spis: [InterruptState::RESET; SPIS],
+            routes: [0; SPIS],
+            redistributors: mpidrs.map(|mpidr| VirtualRedistributor {
+                mpidr,
+                waker: Self::WAKER_PROCESSOR_SLEEP | Self::WAKER_CHILDREN_ASLEEP,
+                private,
+            }),
+        }
+    }
+
+    /// Returns the state of the given interrupt. For SGIs and PPIs the vCPU must be given,
+    /// otherwise it is ignored.
+    ///
+    /// Returns `None` if the interrupt ID is not supported by this model.
+    pub fn interrupt_state(&self, vcpu: Option<usize>, intid: IntId) -> Option<&InterruptState> {
+        if intid.is_sgi() || intid.is_ppi() {
+            Some(&self.redistributors[vcpu?].private[intid.0 as usize])
+        } else if intid.is_spi() {
+            self.spis.get((intid.0 - IntId::SPI_START) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn interrupt_state_mut(
+        &mut self,
+        vcpu: Option<usize>,
+        intid: IntId,
+    ) -> Option<&mut InterruptState> {
+        if intid.is_sgi() || intid.is_ppi() {
+            Some(&mut self.redistributors[vcpu?].private[intid.0 as usize])
+        } else if intid.is_spi() {
+            self.spis.get_mut((intid.0 - IntId::SPI_START) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Sets or clears the pending state of the given interrupt, such as when an emulated device
+    /// raises it. For SGIs and PPIs the vCPU must be given, otherwise it is ignored.
+    ///
+    /// Does nothing if the interrupt ID is not supported by this model.
+    pub fn set_pending(&mut self, vcpu: Option<usize>, intid: IntId, pending: bool) {
+        if let Some(state) = self.interrupt_state_mut(vcpu, intid) {
+            state.pending = pending;
+        }
+    }
+
+    /// Handles a guest write of the given value to `ICC_SGI1R_EL1` on the given vCPU, by making
+    /// the SGI pending on each of the target vCPUs.
+    pub fn write_sgi1r(&mut self, vcpu: usize, value: u64) {
+        let intid = IntId::sgi(((value >> 24) & 0b1111) as u32);
+        let all_but_self = value & (1 << 40) != 0;
+        let target_list = value & 0xffff;
+        let range_selector = (value >> 44) & 0b1111;
+        // Convert Aff3, Aff2 and Aff1 to their positions in MPIDR.
+        let affinity = (((value >> 48) & 0xff) << 32)
+            | (((value >> 32) & 0xff) << 16)
+            | (((value >> 16) & 0xff) << 8);
+        for (target, redistributor) in self.redistributors.iter_mut().enumerate() {
+            let targeted = if all_but_self {
+                target != vcpu
+            } else {
+                let aff0 = redistributor.mpidr & 0xff;
+                redistributor.mpidr & Self::AFFINITY_MASK & !0xff == affinity
+                    && aff0 >> 4 == range_selector
+                    && target_list & (1 << (aff0 & 0b1111)) != 0
+            };
+            if targeted {
+                redistributor.private[intid.0 as usize].pending = true;
+            }
+        }
+    }
+
+    /// Returns whether the given SPI is routed to the given vCPU.
+    fn spi_routed_to(&self, spi: usize, vcpu: usize) -> bool {
+        let route = self.routes[spi];
+        route & Self::IROUTER_IRM != 0
+            || route & Self::AFFINITY_MASK == self.redistributors[vcpu].mpidr & Self::AFFINITY_MASK
+    }
+
+    /// Returns the highest priority interrupt which should be injected into the given vCPU, if
+    /// any.
+    ///
+    /// This is an interrupt which is pending, enabled, not active, in an enabled group, and routed
+    /// to the vCPU. After injecting it the caller should call [`VirtualGic::mark_injected`].
+    pub fn next_injection(&self, vcpu: usize) -> Option<Injection> {
+        let group_enabled = |state: &InterruptState| {
+            if state.group1 {
+                self.ctlr & Self::CTLR_ENABLE_GRP1 != 0
+            } else {
+                self.ctlr & Self::CTLR_ENABLE_GRP0 != 0
+            }
+        };
+        let candidate = |state: &InterruptState| {
+            state.pending
+                && state.enabled
+                && !state.active
+                && state.injected.is_none()
+                && group_enabled(state)
+        };
+
+        let private = self.redistributors[vcpu]
+            .private
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (IntId(i as u32), state));
+        let spis = self
+            .spis
+            .iter()
+            .enumerate()
+            .filter(|&(spi, _)| self.spi_routed_to(spi, vcpu))
+            .map(|(spi, state)| (IntId::spi(spi as u32), state));
+        private
+            .chain(spis)
+            .filter(|(_, state)| candidate(state))
+            .min_by_key(|(intid, state)| (state.priority, *intid))
+            .map(|(intid, state)| Injection {
+                intid,
+                priority: state.priority,
+                group1: state.group1,
+            })
+    }
+
+    /// Records that the given interrupt has been injected into the given vCPU, so it is no longer
+    /// pending in the model and won't be returned again by [`VirtualGic::next_injection`] until it
+    /// has been completed.
+    pub fn mark_injected(&mut self, vcpu: usize, intid: IntId) {
+        if let Some(state) = self.interrupt_state_mut(Some(vcpu), intid) {
+            state.pending = false;
+            state.injected = Some(vcpu);
+        }
+    }
+
+    /// Records that the guest has deactivated the given interrupt, which was previously injected
+    /// into the given vCPU.
+    ///
+    /// A level-triggered interrupt whose input is still asserted should be made pending again with
+    /// [`VirtualGic::set_pending`].
+    pub fn complete(&mut self, vcpu: usize, intid: IntId) {
+        if let Some(state) = self.interrupt_state_mut(Some(vcpu), intid) {
+            state.injected = None;
+            state.active = false;
+        }
+    }
+
+    /// Returns an error unless the access width is supported and the offset aligned to it.
+    fn check_access(offset: usize, width: usize, frame_size: usize) -> Result<(), VgicError> {
+        if matches!(width, 1 | 2 | 4 | 8) && offset.is_multiple_of(width) && offset < frame_size {
+            Ok(())
+        } else {
+            Err(VgicError::UnsupportedAccess { offset, width })
+        }
+    }
+
+    fn read_bitmap(&self, vcpu: Option<usize>, register: BitmapRegister, first: u32) -> u32 {
+        let mut value = 0;
+        for i in 0..register.interrupts_per_register() {
+            let Some(state) = self.interrupt_state(vcpu, IntId(first + i)) else {
+                continue;
+            };
+            value |= match register {
+                BitmapRegister::Group => u32::from(state.group1) << i,
+                BitmapRegister::SetEnable | BitmapRegister::ClearEnable => {
+                    u32::from(state.enabled) << i
+                }
+                BitmapRegister::SetPending | BitmapRegister::ClearPending => {
+                    u32::from(state.pending) << i
+                }
+                BitmapRegister::SetActive | BitmapRegister::ClearActive => {
+                    u32::from(state.active || state.injected.is_some()) << i
+                }
+                BitmapRegister::Config => u32::from(state.trigger == Trigger::Edge) << (i * 2 + 1),
+            };
+        }
+        value
+    }
+
+    fn write_bitmap(
+        &mut self,
+        vcpu: Option<usize>,
+        register: BitmapRegister,
+        first: u32,
+        value: u32,
+    ) {
+        for i in 0..register.interrupts_per_register() {
+            let intid = IntId(first + i);
+            let Some(state) = self.interrupt_state_mut(vcpu, intid) else {
+                continue;
+            };
+            let bit = value & (1 << i) != 0;
+            match register {
+                BitmapRegister::Group => state.group1 = bit,
+                BitmapRegister::SetEnable if bit => state.enabled = true,
+                BitmapRegister::ClearEnable if bit => state.enabled = false,
+                BitmapRegister::SetPending if bit => state.pending = true,
+                BitmapRegister::ClearPending if bit => state.pending = false,
+                BitmapRegister::SetActive if bit => state.active = true,
+                BitmapRegister::ClearActive if bit => state.active = false,
+                // SGIs are always edge-triggered.
+                BitmapRegister::Config if !intid.is_sgi() => {
+                    state.trigger = if value & (1 << (i * 2 + 1)) != 0 {
+                        Trigger::Edge
+                    } else {
+                        Trigger::Level
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn read_priorities(&self, vcpu: Option<usize>, first: u32, width: usize) -> u64 {
+        (0..width as u32)
+            .filter_map(|i| {
+                self.interrupt_state(vcpu, IntId(first + i))
+                    .map(|state| u64::from(state.priority) << (i * 8))
+            })
+            .fold(0, |value, priority| value | priority)
+    }
+
+    fn write_priorities(&mut self, vcpu: Option<usize>, first: u32, width: usize, value: u64) {
+        for i in 0..width as u32 {
+            if let Some(state) = self.interrupt_state_mut(vcpu, IntId(first + i)) {
+                state.priority = (value >> (i * 8)) as u8 & Self::PRIORITY_MASK;
+            }
+        }
+    }
+
+    /// Returns the value of `GICD_TYPER`.
+    fn gicd_typer(&self) -> u32 {
+        // 10 interrupt ID bits, 1 of N SPI routing supported, and enough SPIs.
+        (9 << 19) | (SPIS as u32).div_ceil(32)
+    }
+
+    /// Emulates a guest read of the given width in bytes from the given offset in the distributor
+    /// frame.
+    pub fn gicd_read(&self, offset: usize, width: usize) -> Result<u64, VgicError> {
+        Self::check_access(offset, width, size_of::<Gicd>())?;
+
+        match offset {
+            GICD_IPRIORITYR..GICD_IPRIORITYR_END if width == 1 || width == 4 => {
+                Ok(self.read_priorities(None, (offset - GICD_IPRIORITYR) as u32, width))
+            }
+            GICD_IROUTER..GICD_IROUTER_END if width == 4 || width == 8 => {
+                let route = self
+                    .routes
+                    .get((offset - GICD_IROUTER) / 8)
+                    .copied()
+                    .unwrap_or_default();
+                Ok(if width == 8 {
+                    route
+                } else {
+                    route >> ((offset % 8) * 8) & 0xffff_ffff
+                })
+            }
+            _ if width != 4 => Ok(0),
+            GICD_CTLR => Ok((self.ctlr | Self::CTLR_ARE | Self::CTLR_DS).into()),
+            GICD_TYPER => Ok(self.gicd_typer().into()),
+            GICD_IIDR => Ok(Self::IIDR.into()),
+            GICD_PIDR2 => Ok(Self::PIDR2.into()),
+            _ => Ok(find_bitmap(&GICD_BITMAPS, offset)
+                .map(|(register, first)| self.read_bitmap(None, register, first))
+                .unwrap_or_default()
+                .into()),
+        }
+    }
+
+    /// Emulates a guest write of the given value and width in bytes to the given offset in the
+    /// distributor frame.
+    pub fn gicd_write(&mut self, offset: usize, width: usize, value: u64) -> Result<(), VgicError> {
+        Self::check_access(offset, width, size_of::<Gicd>())?;
+
+        match offset {
+            GICD_IPRIORITYR..GICD_IPRIORITYR_END if width == 1 || width == 4 => {
+                self.write_priorities(None, (offset - GICD_IPRIORITYR) as u32, width, value);
+            }
+            GICD_IROUTER..GICD_IROUTER_END if width == 4 || width == 8 => {
+                if let Some(route) = self.routes.get_mut((offset - GICD_IROUTER) / 8) {
+                    let (value, mask) = if width == 8 {
+                        (value, u64::MAX)
+                    } else {
+                        let shift = (offset % 8) * 8;
+                        ((value & 0xffff_ffff) << shift, 0xffff_ffff << shift)
+                    };
+                    *route =
+                        (*route & !mask | value & mask) & (Self::AFFINITY_MASK | Self::IROUTER_IRM);
+                }
+            }
+            _ if width != 4 => {}
+            GICD_CTLR => {
+                self.ctlr = value as u32 & (Self::CTLR_ENABLE_GRP0 | Self::CTLR_ENABLE_GRP1);
+            }
+            _ => {
+                if let Some((register, first)) = find_bitmap(&GICD_BITMAPS, offset) {
+                    self.write_bitmap(None, register, first, value as u32);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value of `GICR_TYPER` for the given vCPU's redistributor.
+    fn gicr_typer(&self, vcpu: usize) -> u64 {
+        let mpidr = self.redistributors[vcpu].mpidr;
+        let affinity = ((mpidr >> 8) & 0xff00_0000) | (mpidr & 0x00ff_ffff);
+        let mut typer = (affinity << 32) | ((vcpu as u64) << 8);
+        if vcpu == VCPUS - 1 {
+            typer |= Self::GICR_TYPER_LAST;
+        }
+        typer
+    }
+
+    /// Emulates a guest read of the given width in bytes from the given offset in the given vCPU's
+    /// redistributor frames, starting from `RD_base`.
+    pub fn gicr_read(&self, vcpu: usize, offset: usize, width: usize) -> Result<u64, VgicError> {
+        Self::check_access(offset, width, size_of::<GicrSgi>())?;
+
+        match offset {
+            GICR_TYPER..GICR_TYPER_END if width == 4 || width == 8 => {
+                let typer = self.gicr_typer(vcpu);
+                Ok(if width == 8 {
+                    typer
+                } else {
+                    typer >> ((offset % 8) * 8) & 0xffff_ffff
+                })
+            }
+            GICR_IPRIORITYR..GICR_IPRIORITYR_END if width == 1 || width == 4 => {
+                Ok(self.read_priorities(Some(vcpu), (offset - GICR_IPRIORITYR) as u32, width))
+            }
+            _ if width != 4 => Ok(0),
+            GICR_IIDR => Ok(Self::IIDR.into()),
+            GICR_WAKER => Ok(self.redistributors[vcpu].waker.into()),
+            GICR_PIDR2 => Ok(Self::PIDR2.into()),
+            _ => Ok(find_bitmap(&GICR_BITMAPS, offset)
+                .map(|(register, first)| self.read_bitmap(Some(vcpu), register, first))
+                .unwrap_or_default()
+                .into()),
+        }
+    }
+
+    /// Emulates a guest write of the given value and width in bytes to the given offset in the
+    /// given vCPU's redistributor frames, starting from `RD_base`.
+    pub fn gicr_write(
+        &mut self,
+        vcpu: usize,
+        offset: usize,
+        width: usize,
+        value: u64,
+    ) -> Result<(), VgicError> {
+        Self::check_access(offset, width, size_of::<GicrSgi>())?;
+
+        match offset {
+            GICR_IPRIORITYR..GICR_IPRIORITYR_END if width == 1 || width == 4 => {
+                self.write_priorities(Some(vcpu), (offset - GICR_IPRIORITYR) as u32, width, value);
+            }
+            _ if width != 4 => {}
+            GICR_WAKER => {
+                // The redistributor wakes up or goes to sleep immediately.
+                let sleep = value as u32 & Self::WAKER_PROCESSOR_SLEEP;
+                self.redistributors[vcpu].waker = if sleep != 0 {
+                    Self::WAKER_PROCESSOR_SLEEP | Self::WAKER_CHILDREN_ASLEEP
+                } else {
+                    0
+                };
+            }
+            _ => {
+                if let Some((register, first)) = find_bitmap(&GICR_BITMAPS, offset) {
+                    self.write_bitmap(Some(vcpu), register, first, value as u32);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestGic = VirtualGic<2, 64>;
+
+    #[test]
+    fn gicd_id_registers() {
+        let gic = TestGic::new([0, 1]);
+        assert_eq!(gic.gicd_read(0x4, 4), Ok(0x0048_0002));
+        assert_eq!(gic.gicd_read(0xffe8, 4), Ok(0x3b));
+        assert_eq!(
+            gic.gicd_read(0x3, 4),
+            Err(VgicError::UnsupportedAccess {
+                offset: 0x3,
+                width: 4
+            })
+        );
+    }
+
+    #[test]
+    fn gicr_typer() {
+        let gic = TestGic::new([0x0000_0000, 0x01_0002_0301]);
+        assert_eq!(gic.gicr_read(0, 0x8, 8), Ok(0x0000_0000_0000_0000));
+        assert_eq!(gic.gicr_read(1, 0x8, 8), Ok(0x0102_0301_0000_0110));
+        assert_eq!(gic.gicr_read(1, 0xc, 4), Ok(0x0102_0301));
+    }
+
+    #[test]
+    fn inject_spi() {
+        let mut gic = TestGic::new([0, 1]);
+        let intid = IntId::spi(3);
+        gic.gicd_write(0x0, 4, 0b10).unwrap();
+        gic.gicd_write(0x84, 4, 1 << 3).unwrap();
+        gic.gicd_write(0x104, 4, 1 << 3).unwrap();
+        gic.gicd_write(0x400 + 35, 1, 0x80).unwrap();
+        // Route the SPI to vCPU 1.
+        gic.gicd_write(0x6100 + 3 * 8, 8, 1).unwrap();
+        gic.set_pending(None, intid, true);
+
+        assert_eq!(gic.next_injection(0), None);
+        assert_eq!(
+            gic.next_injection(1),
+            Some(Injection {
+                intid,
+                priority: 0x80,
+                group1: true
+            })
+        );
+
+        gic.mark_injected(1, intid);
+        assert_eq!(gic.next_injection(1), None);
+        assert_eq!(gic.gicd_read(0x304, 4), Ok(1 << 3));
+        gic.complete(1, intid);
+        assert_eq!(gic.gicd_read(0x304, 4), Ok(0));
+    }
+
+    #[test]
+    fn inject_highest_priority() {
+        let mut gic = TestGic::new([0, 1]);
+        gic.gicd_write(0x0, 4, 0b11).unwrap();
+        // Enable SGI 1 and PPI 0 on vCPU 0, with PPI 0 at higher priority.
+        gic.gicr_write(0, 0x10100, 4, (1 << 1) | (1 << 16)).unwrap();
+        gic.gicr_write(0, 0x10400, 4, 0x0000_a000).unwrap();
+        gic.gicr_write(0, 0x10410, 4, 0x0000_0040).unwrap();
+        gic.write_sgi1r(1, (1 << 24) | 0b1);
+        gic.set_pending(Some(0), IntId::ppi(0), true);
+
+        assert_eq!(gic.next_injection(1), None);
+        assert_eq!(gic.next_injection(0).unwrap().intid, IntId::ppi(0));
+        gic.mark_injected(0, IntId::ppi(0));
+        assert_eq!(gic.next_injection(0).unwrap().intid, IntId::sgi(1));
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2026 The arm-gic Authors.
+// This project is dual-licensed under Apache 2.0 and MIT terms.
+// See LICENSE-APACHE and LICENSE-MIT for details.
+
+//! Types shared by the hypervisor support for GICv2 and GICv3.
+//!
+//! These are re-exported by [`gicv2::hypervisor`](crate::gicv2::hypervisor) and
+//! `gicv3::hypervisor`, which each encode them in their own list register format.
+
+use crate::IntId;
+use thiserror::Error;
+
+/// An error which may be returned when managing the list registers of a virtual CPU interface.
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HypervisorError {
+    #[error("All list registers are in use")]
+    NoFreeListRegister,
+    #[error("{0:?} is already in a list register")]
+    AlreadyQueued(IntId),
+    #[error("{0:?} can't be linked to a physical interrupt")]
+    InvalidPhysicalInterrupt(IntId),
+}
+
+/// The state of a virtual interrupt in a list register.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ListRegisterState {
+    /// The list register doesn't contain a valid interrupt.
+    Invalid = 0b00,
+    /// The interrupt is pending.
+    Pending = 0b01,
+    /// The interrupt is active.
+    Active = 0b10,
+    /// The interrupt is both pending and active.
+    PendingAndActive = 0b11,
+}
+
+impl ListRegisterState {
+    /// Decodes the 2-bit State field of a list register.
+    pub(crate) fn from_bits(bits: u32) -> Self {
+        match bits & 0b11 {
+            0b00 => Self::Invalid,
+            0b01 => Self::Pending,
+            0b10 => Self::Active,
+            _ => Self::PendingAndActive,
+        }
+    }
+}
+
+/// A virtual interrupt to be presented to a VM by a list register.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VirtualInterrupt {
+    /// The virtual interrupt ID seen by the VM.
+    pub intid: IntId,
+    /// The priority of the virtual interrupt. Only as many of the top bits as the list registers
+    /// implement are used, which is 5 on GICv2.
+    pub priority: u8,
+    /// Whether the virtual interrupt is in group 1 rather than group 0.
+    pub group1: bool,
+    /// The physical interrupt which this virtual interrupt is linked to, if any. This must be a PPI
+    /// or SPI. On GICv3 extended PPIs and extended SPIs are also allowed; GICv2 has neither.
+    ///
+    /// When the VM deactivates the virtual interrupt the physical interrupt is deactivated too,
+    /// rather than a maintenance interrupt being generated.
+    pub physical: Option<IntId>,
+    /// For a GICv2 virtual SGI which isn't linked to a physical interrupt, the CPU interface
+    /// number reported to the VM as the source of the SGI. This is ignored on GICv3.
+    pub source_cpu: u8,
+}
+
+/// The result of handling a maintenance interrupt, with the maintenance interrupt status register
+/// type `Misr` of the GIC version.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Maintenance<Misr> {
+    /// The reasons for the maintenance interrupt.
+    pub misr: Misr,
+    /// The virtual interrupts which the VM has deactivated.
+    pub eoied: EoiedInterrupts,
+}
+
+/// An iterator over the virtual interrupts which the VM has deactivated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EoiedInterrupts {
+    /// A bit for each list register which hasn't yet been returned.
+    pub(crate) mask: u64,
+    /// The virtual interrupt ID which each list register contained.
+    pub(crate) intids: [IntId; 64],
+}
+
+impl Iterator for EoiedInterrupts {
+    type Item = IntId;
+
+    fn next(&mut self) -> Option<IntId> {
+        if self.mask == 0 {
+            return None;
+        }
+        let n = self.mask.trailing_zeros() as usize;
+        self.mask &= !(1 << n);
+        Some(self.intids[n])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_register_state() {
+        assert_eq!(
+            ListRegisterState::from_bits(0b00),
+            ListRegisterState::Invalid
+        );
+        assert_eq!(
+            ListRegisterState::from_bits(0b01),
+            ListRegisterState::Pending
+        );
+        assert_eq!(
+            ListRegisterState::from_bits(0b10),
+            ListRegisterState::Active
+        );
+        assert_eq!(
+            ListRegisterState::from_bits(0b11),
+            ListRegisterState::PendingAndActive
+        );
+    }
+
+    #[test]
+    fn eoied_interrupts() {
+        let mut intids = [IntId(0); 64];
+        intids[1] = IntId::spi(4);
+        intids[5] = IntId::ppi(2);
+        intids[63] = IntId::sgi(7);
+        let eoied = EoiedInterrupts {
+            mask: (1 << 63) | (1 << 5) | (1 << 1),
+            intids,
+        };
+        assert!(eoied.eq([IntId::spi(4), IntId::ppi(2), IntId::sgi(7)]));
+    }
+}
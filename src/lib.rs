@@ -32,8 +32,8 @@
 //! // Configure an SGI and then send it to ourself.
 //! let sgi_intid = IntId::sgi(3);
 //! GicV3::set_priority_mask(0xff);
-//! gic.set_interrupt_priority(sgi_intid, Some(0), 0x80);
-//! gic.enable_interrupt(sgi_intid, Some(0), true);
+//! gic.set_interrupt_priority(sgi_intid, Some(0), 0x80).unwrap();
+//! gic.enable_interrupt(sgi_intid, Some(0), true).unwrap();
 //! irq_enable();
 //! GicV3::send_sgi(
 //!     sgi_intid,
@@ -53,6 +53,7 @@
 
 pub mod gicv2;
 pub mod gicv3;
+pub mod hypervisor;
 #[cfg(any(test, feature = "fakes", target_arch = "aarch64", target_arch = "arm"))]
 mod sysreg;
 
@@ -64,6 +65,11 @@ pub use sysreg::fake::{irq_disable, irq_enable, wfi};
 #[cfg(all(target_arch = "aarch64", not(feature = "fakes")))]
 use core::arch::asm;
 use core::fmt::{Debug, Formatter, Result};
+#[cfg(any(
+    feature = "fakes",
+    not(any(target_arch = "aarch64", target_arch = "arm"))
+))]
+use core::sync::atomic::{Ordering, fence};
 
 /// The trigger configuration for an interrupt.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -74,6 +80,15 @@ pub enum Trigger {
     Level,
 }
 
+/// A message which a device can write to signal an interrupt, such as a PCIe MSI.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MsiMessage {
+    /// The physical address to which the message should be written.
+    pub address: u64,
+    /// The value to write.
+    pub data: u32,
+}
+
 /// An interrupt ID.
 #[derive(Copy, Clone, Eq, Ord, PartialOrd, PartialEq)]
 pub struct IntId(u32);
@@ -184,9 +199,15 @@ impl IntId {
         Self::PPI_START <= self.0 && self.0 < Self::SPI_START
     }
 
-    /// Returns whether this interrupt ID is private to a core, i.e. it is an SGI or PPI.
+    /// Returns whether this interrupt ID is for an extended Private Peripheral Interrupt.
+    pub const fn is_eppi(self) -> bool {
+        Self::EPPI_START <= self.0 && self.0 < Self::EPPI_END
+    }
+
+    /// Returns whether this interrupt ID is private to a core, i.e. it is an SGI, PPI or extended
+    /// PPI.
     pub const fn is_private(self) -> bool {
-        self.is_sgi() || self.is_ppi()
+        self.is_sgi() || self.is_ppi() || self.is_eppi()
     }
 
     /// Returns whether this interrupt ID is for a Shared Peripheral Interrupt.
@@ -194,6 +215,16 @@ impl IntId {
         Self::SPI_START <= self.0 && self.0 < Self::SPECIAL_START
     }
 
+    /// Returns whether this interrupt ID is for an extended Shared Peripheral Interrupt.
+    pub const fn is_espi(self) -> bool {
+        Self::ESPI_START <= self.0 && self.0 < Self::ESPI_END
+    }
+
+    /// Returns whether this interrupt ID is for a Locality-specific Peripheral Interrupt.
+    pub const fn is_lpi(self) -> bool {
+        Self::LPI_START <= self.0
+    }
+
     // TODO: Change this to return a Range<IntId> once core::iter::Step is stabilised.
     /// Returns an array of all interrupt Ids that are private to a core, i.e. SGIs and PPIs.
     pub fn private() -> impl Iterator<Item = IntId> {
@@ -242,6 +273,20 @@ impl From<IntId> for u32 {
     }
 }
 
+/// Returns the number of preemption levels available with the given number of implemented priority
+/// bits, when the group priority field is bits `[7:group_priority_lsb]` of each priority.
+///
+/// Priority bits are implemented from the most significant bit down, so only the implemented bits
+/// of the group priority field distinguish preemption levels.
+const fn preemption_levels(priority_bits: u32, group_priority_lsb: u32) -> u32 {
+    let group_priority_bits = 8 - group_priority_lsb;
+    if priority_bits < group_priority_bits {
+        1 << priority_bits
+    } else {
+        1 << group_priority_bits
+    }
+}
+
 /// Disables debug, SError, IRQ and FIQ exceptions.
 #[cfg(all(target_arch = "aarch64", not(feature = "fakes")))]
 pub fn irq_disable() {
@@ -268,3 +313,37 @@ pub fn wfi() {
         asm!("wfi", options(nomem, nostack));
     }
 }
+
+/// Waits until all memory writes before this point have completed, so that they are visible to the
+/// GIC before any following register writes.
+pub(crate) fn dsb_st() {
+    #[cfg(all(
+        any(target_arch = "aarch64", target_arch = "arm"),
+        not(feature = "fakes")
+    ))]
+    // SAFETY: A barrier doesn't access memory in any way.
+    unsafe {
+        core::arch::asm!("dsb st", options(nostack));
+    }
+    #[cfg(any(
+        feature = "fakes",
+        not(any(target_arch = "aarch64", target_arch = "arm"))
+    ))]
+    fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preemption_levels_limited_by_priority_bits() {
+        // With 5 priority bits, only bits [7:3] are implemented.
+        assert_eq!(preemption_levels(5, 0), 32);
+        assert_eq!(preemption_levels(5, 3), 32);
+        assert_eq!(preemption_levels(5, 4), 16);
+        assert_eq!(preemption_levels(5, 7), 2);
+        assert_eq!(preemption_levels(5, 8), 1);
+        assert_eq!(preemption_levels(8, 1), 128);
+    }
+}
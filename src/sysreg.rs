@@ -14,18 +14,101 @@ mod aarch64;
 #[macro_use]
 mod aarch32;
 
+read_sysreg32!(icc_ap0r0_el1, 0, c12, c8, 4, read_icc_ap0r0_el1);
+read_sysreg32!(icc_ap0r1_el1, 0, c12, c8, 5, read_icc_ap0r1_el1);
+read_sysreg32!(icc_ap0r2_el1, 0, c12, c8, 6, read_icc_ap0r2_el1);
+read_sysreg32!(icc_ap0r3_el1, 0, c12, c8, 7, read_icc_ap0r3_el1);
+read_sysreg32!(icc_ap1r0_el1, 0, c12, c9, 0, read_icc_ap1r0_el1);
+read_sysreg32!(icc_ap1r1_el1, 0, c12, c9, 1, read_icc_ap1r1_el1);
+read_sysreg32!(icc_ap1r2_el1, 0, c12, c9, 2, read_icc_ap1r2_el1);
+read_sysreg32!(icc_ap1r3_el1, 0, c12, c9, 3, read_icc_ap1r3_el1);
+read_sysreg32!(icc_bpr0_el1, 0, c12, c8, 3, read_icc_bpr0_el1);
+read_sysreg32!(icc_bpr1_el1, 0, c12, c12, 3, read_icc_bpr1_el1);
+read_sysreg32!(icc_ctlr_el1, 0, c12, c12, 4, read_icc_ctlr_el1);
 read_sysreg32!(icc_hppir0_el1, 0, c12, c8, 2, read_icc_hppir0_el1);
 read_sysreg32!(icc_hppir1_el1, 0, c12, c12, 2, read_icc_hppir1_el1);
 read_sysreg32!(icc_iar0_el1, 0, c12, c8, 0, read_icc_iar0_el1);
 read_sysreg32!(icc_iar1_el1, 0, c12, c12, 0, read_icc_iar1_el1);
+// ICC_NMIAR1_EL1 has no AArch32 equivalent.
+#[cfg(any(test, feature = "fakes", target_arch = "aarch64"))]
+read_sysreg32!(generic icc_nmiar1_el1, 0, c12, c9, 5, read_icc_nmiar1_el1);
+read_sysreg32!(icc_rpr_el1, 0, c12, c11, 3, read_icc_rpr_el1);
+read_sysreg32!(ich_ap0r0_el2, 4, c12, c8, 0, read_ich_ap0r0_el2);
+read_sysreg32!(ich_ap0r1_el2, 4, c12, c8, 1, read_ich_ap0r1_el2);
+read_sysreg32!(ich_ap0r2_el2, 4, c12, c8, 2, read_ich_ap0r2_el2);
+read_sysreg32!(ich_ap0r3_el2, 4, c12, c8, 3, read_ich_ap0r3_el2);
+read_sysreg32!(ich_ap1r0_el2, 4, c12, c9, 0, read_ich_ap1r0_el2);
+read_sysreg32!(ich_ap1r1_el2, 4, c12, c9, 1, read_ich_ap1r1_el2);
+read_sysreg32!(ich_ap1r2_el2, 4, c12, c9, 2, read_ich_ap1r2_el2);
+read_sysreg32!(ich_ap1r3_el2, 4, c12, c9, 3, read_ich_ap1r3_el2);
+read_sysreg32!(ich_eisr_el2, 4, c12, c11, 3, read_ich_eisr_el2);
+read_sysreg32!(ich_elrsr_el2, 4, c12, c11, 5, read_ich_elrsr_el2);
+read_sysreg32!(ich_hcr_el2, 4, c12, c11, 0, read_ich_hcr_el2);
+read_sysreg32!(ich_misr_el2, 4, c12, c11, 2, read_ich_misr_el2);
+read_sysreg32!(ich_vmcr_el2, 4, c12, c11, 7, read_ich_vmcr_el2);
+read_sysreg32!(ich_vtr_el2, 4, c12, c11, 1, read_ich_vtr_el2);
 
+write_sysreg32!(icc_ap0r0_el1, 0, c12, c8, 4, write_icc_ap0r0_el1);
+write_sysreg32!(icc_ap0r1_el1, 0, c12, c8, 5, write_icc_ap0r1_el1);
+write_sysreg32!(icc_ap0r2_el1, 0, c12, c8, 6, write_icc_ap0r2_el1);
+write_sysreg32!(icc_ap0r3_el1, 0, c12, c8, 7, write_icc_ap0r3_el1);
+write_sysreg32!(icc_ap1r0_el1, 0, c12, c9, 0, write_icc_ap1r0_el1);
+write_sysreg32!(icc_ap1r1_el1, 0, c12, c9, 1, write_icc_ap1r1_el1);
+write_sysreg32!(icc_ap1r2_el1, 0, c12, c9, 2, write_icc_ap1r2_el1);
+write_sysreg32!(icc_ap1r3_el1, 0, c12, c9, 3, write_icc_ap1r3_el1);
+write_sysreg32!(icc_bpr0_el1, 0, c12, c8, 3, write_icc_bpr0_el1);
+write_sysreg32!(icc_bpr1_el1, 0, c12, c12, 3, write_icc_bpr1_el1);
 write_sysreg32!(icc_ctlr_el1, 0, c12, c12, 4, write_icc_ctlr_el1);
+write_sysreg32!(icc_dir_el1, 0, c12, c11, 1, write_icc_dir_el1);
 write_sysreg32!(icc_eoir0_el1, 0, c12, c8, 1, write_icc_eoir0_el1);
 write_sysreg32!(icc_eoir1_el1, 0, c12, c12, 1, write_icc_eoir1_el1);
 write_sysreg32!(icc_igrpen0_el1, 0, c12, c12, 6, write_icc_igrpen0_el1);
 write_sysreg32!(icc_igrpen1_el1, 0, c12, c12, 7, write_icc_igrpen1_el1);
 write_sysreg32!(icc_pmr_el1, 0, c4, c6, 0, write_icc_pmr_el1);
 write_sysreg32!(icc_sre_el1, 0, c12, c12, 5, write_icc_sre_el1);
+write_sysreg32!(ich_ap0r0_el2, 4, c12, c8, 0, write_ich_ap0r0_el2);
+write_sysreg32!(ich_ap0r1_el2, 4, c12, c8, 1, write_ich_ap0r1_el2);
+write_sysreg32!(ich_ap0r2_el2, 4, c12, c8, 2, write_ich_ap0r2_el2);
+write_sysreg32!(ich_ap0r3_el2, 4, c12, c8, 3, write_ich_ap0r3_el2);
+write_sysreg32!(ich_ap1r0_el2, 4, c12, c9, 0, write_ich_ap1r0_el2);
+write_sysreg32!(ich_ap1r1_el2, 4, c12, c9, 1, write_ich_ap1r1_el2);
+write_sysreg32!(ich_ap1r2_el2, 4, c12, c9, 2, write_ich_ap1r2_el2);
+write_sysreg32!(ich_ap1r3_el2, 4, c12, c9, 3, write_ich_ap1r3_el2);
+write_sysreg32!(ich_hcr_el2, 4, c12, c11, 0, write_ich_hcr_el2);
+write_sysreg32!(ich_vmcr_el2, 4, c12, c11, 7, write_ich_vmcr_el2);
 write_sysreg64!(icc_asgi1r_el1, 0, c12, write_icc_asgi1r_el1);
 write_sysreg64!(icc_sgi0r_el1, 0, c12, write_icc_sgi0r_el1);
 write_sysreg64!(icc_sgi1r_el1, 0, c12, write_icc_sgi1r_el1);
+
+read_sysreg64_split!(ich_lr0_el2, 4, c12, c12, c14, 0, read_ich_lr0_el2);
+read_sysreg64_split!(ich_lr1_el2, 4, c12, c12, c14, 1, read_ich_lr1_el2);
+read_sysreg64_split!(ich_lr2_el2, 4, c12, c12, c14, 2, read_ich_lr2_el2);
+read_sysreg64_split!(ich_lr3_el2, 4, c12, c12, c14, 3, read_ich_lr3_el2);
+read_sysreg64_split!(ich_lr4_el2, 4, c12, c12, c14, 4, read_ich_lr4_el2);
+read_sysreg64_split!(ich_lr5_el2, 4, c12, c12, c14, 5, read_ich_lr5_el2);
+read_sysreg64_split!(ich_lr6_el2, 4, c12, c12, c14, 6, read_ich_lr6_el2);
+read_sysreg64_split!(ich_lr7_el2, 4, c12, c12, c14, 7, read_ich_lr7_el2);
+read_sysreg64_split!(ich_lr8_el2, 4, c12, c13, c15, 0, read_ich_lr8_el2);
+read_sysreg64_split!(ich_lr9_el2, 4, c12, c13, c15, 1, read_ich_lr9_el2);
+read_sysreg64_split!(ich_lr10_el2, 4, c12, c13, c15, 2, read_ich_lr10_el2);
+read_sysreg64_split!(ich_lr11_el2, 4, c12, c13, c15, 3, read_ich_lr11_el2);
+read_sysreg64_split!(ich_lr12_el2, 4, c12, c13, c15, 4, read_ich_lr12_el2);
+read_sysreg64_split!(ich_lr13_el2, 4, c12, c13, c15, 5, read_ich_lr13_el2);
+read_sysreg64_split!(ich_lr14_el2, 4, c12, c13, c15, 6, read_ich_lr14_el2);
+read_sysreg64_split!(ich_lr15_el2, 4, c12, c13, c15, 7, read_ich_lr15_el2);
+write_sysreg64_split!(ich_lr0_el2, 4, c12, c12, c14, 0, write_ich_lr0_el2);
+write_sysreg64_split!(ich_lr1_el2, 4, c12, c12, c14, 1, write_ich_lr1_el2);
+write_sysreg64_split!(ich_lr2_el2, 4, c12, c12, c14, 2, write_ich_lr2_el2);
+write_sysreg64_split!(ich_lr3_el2, 4, c12, c12, c14, 3, write_ich_lr3_el2);
+write_sysreg64_split!(ich_lr4_el2, 4, c12, c12, c14, 4, write_ich_lr4_el2);
+write_sysreg64_split!(ich_lr5_el2, 4, c12, c12, c14, 5, write_ich_lr5_el2);
+write_sysreg64_split!(ich_lr6_el2, 4, c12, c12, c14, 6, write_ich_lr6_el2);
+write_sysreg64_split!(ich_lr7_el2, 4, c12, c12, c14, 7, write_ich_lr7_el2);
+write_sysreg64_split!(ich_lr8_el2, 4, c12, c13, c15, 0, write_ich_lr8_el2);
+write_sysreg64_split!(ich_lr9_el2, 4, c12, c13, c15, 1, write_ich_lr9_el2);
+write_sysreg64_split!(ich_lr10_el2, 4, c12, c13, c15, 2, write_ich_lr10_el2);
+write_sysreg64_split!(ich_lr11_el2, 4, c12, c13, c15, 3, write_ich_lr11_el2);
+write_sysreg64_split!(ich_lr12_el2, 4, c12, c13, c15, 4, write_ich_lr12_el2);
+write_sysreg64_split!(ich_lr13_el2, 4, c12, c13, c15, 5, write_ich_lr13_el2);
+write_sysreg64_split!(ich_lr14_el2, 4, c12, c13, c15, 6, write_ich_lr14_el2);
+write_sysreg64_split!(ich_lr15_el2, 4, c12, c13, c15, 7, write_ich_lr15_el2);
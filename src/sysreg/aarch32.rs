@@ -96,3 +96,101 @@ macro_rules! write_sysreg64 {
         }
     };
 }
+
+/// Generates a safe public function named `$function_name` to read the 64-bit system register
+/// `$sysreg`, which is split into two 32-bit registers on aarch32 with the given `CRm` values for
+/// the low and high halves.
+///
+/// This should only be used for system registers which are indeed safe to read.
+macro_rules! read_sysreg64_split {
+    (
+        $sysreg:ident,
+        $opc1:literal,
+        $crn:ident,
+        $crm_low:ident,
+        $crm_high:ident,
+        $opc2:literal,
+        $function_name:ident
+    ) => {
+        #[inline]
+        #[doc = "Autogenerated function to read the 64-bit "]
+        #[doc = stringify!($sysreg)]
+        #[doc = " system register"]
+        pub fn $function_name() -> u64 {
+            let value_lo: u32;
+            let value_hi: u32;
+            // SAFETY: The caller of the macro guarantees that this system register is safe to read.
+            unsafe {
+                core::arch::asm!(
+                    concat!(
+                        "mrc p15, ",
+                        stringify!($opc1), ",",
+                        "{value_lo}, ",
+                        stringify!($crn), ",",
+                        stringify!($crm_low), ",",
+                        stringify!($opc2), "\n",
+                        "mrc p15, ",
+                        stringify!($opc1), ",",
+                        "{value_hi}, ",
+                        stringify!($crn), ",",
+                        stringify!($crm_high), ",",
+                        stringify!($opc2)
+                    ),
+                    options(nostack),
+                    value_lo = out(reg) value_lo,
+                    value_hi = out(reg) value_hi,
+                );
+            }
+            (u64::from(value_hi) << 32) | u64::from(value_lo)
+        }
+    };
+}
+
+/// Generates a safe public function named `$function_name` to write to the 64-bit system register
+/// `$sysreg`, which is split into two 32-bit registers on aarch32 with the given `CRm` values for
+/// the low and high halves.
+///
+/// This should only be used for system registers which are indeed safe to write.
+macro_rules! write_sysreg64_split {
+    (
+        $sysreg:ident,
+        $opc1:literal,
+        $crn:ident,
+        $crm_low:ident,
+        $crm_high:ident,
+        $opc2:literal,
+        $function_name:ident
+    ) => {
+        #[inline]
+        #[doc = "Autogenerated function to write the 64-bit "]
+        #[doc = stringify!($sysreg)]
+        #[doc = " system register"]
+        pub fn $function_name(value: u64) {
+            let value_lo = value as u32;
+            let value_hi = (value >> 32) as u32;
+            // SAFETY: The caller of the macro guarantees that this system register is safe to
+            // write.
+            unsafe {
+                core::arch::asm!(
+                    concat!(
+                        "mcr p15, ",
+                        stringify!($opc1), ",",
+                        "{value_lo}, ",
+                        stringify!($crn), ",",
+                        stringify!($crm_low), ",",
+                        stringify!($opc2), "\n",
+                        "mcr p15, ",
+                        stringify!($opc1), ",",
+                        "{value_hi}, ",
+                        stringify!($crn), ",",
+                        stringify!($crm_high), ",",
+                        stringify!($opc2)
+                    ),
+                    options(nostack),
+                    value_lo = in(reg) value_lo,
+                    value_hi = in(reg) value_hi,
+                );
+            }
+        }
+    };
+}
@@ -24,6 +24,32 @@ macro_rules! read_sysreg32 {
             value as u32
         }
     };
+    // Uses the generic `S3_<op1>_C<n>_C<m>_<op2>` name, for system registers which the assembler
+    // only knows about when some target feature is enabled.
+    (generic $sysreg:ident, $opc1:literal, $crn:ident, $crm:ident, $opc2: literal, $function_name:ident) => {
+        #[inline]
+        #[doc = "Autogenerated function to read the 32-bit "]
+        #[doc = stringify!($sysreg)]
+        #[doc = " system register"]
+        pub fn $function_name() -> u32 {
+            let value: u64;
+            // SAFETY: The caller of the macro guarantees that this system register is safe to read.
+            unsafe {
+                core::arch::asm!(
+                    concat!(
+                        "mrs {value}, s3_",
+                        stringify!($opc1), "_",
+                        stringify!($crn), "_",
+                        stringify!($crm), "_",
+                        stringify!($opc2)
+                    ),
+                    options(nostack),
+                    value = out(reg) value,
+                );
+            }
+            value as u32
+        }
+    };
 }
 
 /// Generates a safe public function named `$function_name` to write to the 32-bit system register
@@ -73,3 +99,68 @@ macro_rules! write_sysreg64 {
         }
     };
 }
+
+/// Generates a safe public function named `$function_name` to read the 64-bit system register
+/// `$sysreg`, which is split into two 32-bit registers on aarch32.
+///
+/// This should only be used for system registers which are indeed safe to read.
+macro_rules! read_sysreg64_split {
+    (
+        $sysreg:ident,
+        $opc1:literal,
+        $crn:ident,
+        $crm_low:ident,
+        $crm_high:ident,
+        $opc2:literal,
+        $function_name:ident
+    ) => {
+        #[inline]
+        #[doc = "Autogenerated function to read the 64-bit "]
+        #[doc = stringify!($sysreg)]
+        #[doc = " system register"]
+        pub fn $function_name() -> u64 {
+            let value: u64;
+            // SAFETY: The caller of the macro guarantees that this system register is safe to read.
+            unsafe {
+                core::arch::asm!(
+                    concat!("mrs {value}, ", stringify!($sysreg)),
+                    options(nostack),
+                    value = out(reg) value,
+                );
+            }
+            value
+        }
+    };
+}
+
+/// Generates a safe public function named `$function_name` to write to the 64-bit system register
+/// `$sysreg`, which is split into two 32-bit registers on aarch32.
+///
+/// This should only be used for system registers which are indeed safe to write.
+macro_rules! write_sysreg64_split {
+    (
+        $sysreg:ident,
+        $opc1:literal,
+        $crn:ident,
+        $crm_low:ident,
+        $crm_high:ident,
+        $opc2:literal,
+        $function_name:ident
+    ) => {
+        #[inline]
+        #[doc = "Autogenerated function to write the 64-bit "]
+        #[doc = stringify!($sysreg)]
+        #[doc = " system register"]
+        pub fn $function_name(value: u64) {
+            // SAFETY: The caller of the macro guarantees that this system register is safe to
+            // write.
+            unsafe {
+                core::arch::asm!(
+                    concat!("msr ", stringify!($sysreg), ", {value}"),
+                    options(nostack),
+                    value = in(reg) value,
+                );
+            }
+        }
+    };
+}
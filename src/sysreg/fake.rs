@@ -5,6 +5,8 @@
 //! Fake implementations of system register getters and setters for unit tests.
 
 use std::sync::Mutex;
+#[cfg(test)]
+use std::sync::{MutexGuard, PoisonError};
 
 /// Values of fake system registers.
 pub static SYSREGS: Mutex<SystemRegisters> = Mutex::new(SystemRegisters::new());
@@ -12,10 +14,22 @@ pub static SYSREGS: Mutex<SystemRegisters> = Mutex::new(SystemRegisters::new());
 /// A set of fake system registers.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SystemRegisters {
+    pub icc_ap0r0_el1: u32,
+    pub icc_ap0r1_el1: u32,
+    pub icc_ap0r2_el1: u32,
+    pub icc_ap0r3_el1: u32,
+    pub icc_ap1r0_el1: u32,
+    pub icc_ap1r1_el1: u32,
+    pub icc_ap1r2_el1: u32,
+    pub icc_ap1r3_el1: u32,
     pub icc_asgi1r_el1: u64,
     pub icc_iar0_el1: u32,
     pub icc_iar1_el1: u32,
+    pub icc_nmiar1_el1: u32,
+    pub icc_bpr0_el1: u32,
+    pub icc_bpr1_el1: u32,
     pub icc_ctlr_el1: u32,
+    pub icc_dir_el1: u32,
     pub icc_eoir0_el1: u32,
     pub icc_eoir1_el1: u32,
     pub icc_hppir0_el1: u32,
@@ -23,19 +37,62 @@ pub struct SystemRegisters {
     pub icc_igrpen0_el1: u32,
     pub icc_igrpen1_el1: u32,
     pub icc_pmr_el1: u32,
+    pub icc_rpr_el1: u32,
     pub icc_sgi0r_el1: u64,
     pub icc_sgi1r_el1: u64,
     pub icc_sre_el1: u32,
+    pub ich_ap0r0_el2: u32,
+    pub ich_ap0r1_el2: u32,
+    pub ich_ap0r2_el2: u32,
+    pub ich_ap0r3_el2: u32,
+    pub ich_ap1r0_el2: u32,
+    pub ich_ap1r1_el2: u32,
+    pub ich_ap1r2_el2: u32,
+    pub ich_ap1r3_el2: u32,
+    pub ich_eisr_el2: u32,
+    pub ich_elrsr_el2: u32,
+    pub ich_hcr_el2: u32,
+    pub ich_lr0_el2: u64,
+    pub ich_lr1_el2: u64,
+    pub ich_lr2_el2: u64,
+    pub ich_lr3_el2: u64,
+    pub ich_lr4_el2: u64,
+    pub ich_lr5_el2: u64,
+    pub ich_lr6_el2: u64,
+    pub ich_lr7_el2: u64,
+    pub ich_lr8_el2: u64,
+    pub ich_lr9_el2: u64,
+    pub ich_lr10_el2: u64,
+    pub ich_lr11_el2: u64,
+    pub ich_lr12_el2: u64,
+    pub ich_lr13_el2: u64,
+    pub ich_lr14_el2: u64,
+    pub ich_lr15_el2: u64,
+    pub ich_misr_el2: u32,
+    pub ich_vmcr_el2: u32,
+    pub ich_vtr_el2: u32,
     pub daif: u64,
 }
 
 impl SystemRegisters {
     const fn new() -> Self {
         Self {
+            icc_ap0r0_el1: 0,
+            icc_ap0r1_el1: 0,
+            icc_ap0r2_el1: 0,
+            icc_ap0r3_el1: 0,
+            icc_ap1r0_el1: 0,
+            icc_ap1r1_el1: 0,
+            icc_ap1r2_el1: 0,
+            icc_ap1r3_el1: 0,
             icc_asgi1r_el1: 0,
             icc_iar0_el1: 0,
             icc_iar1_el1: 0,
+            icc_nmiar1_el1: 0,
+            icc_bpr0_el1: 0,
+            icc_bpr1_el1: 0,
             icc_ctlr_el1: 0,
+            icc_dir_el1: 0,
             icc_eoir0_el1: 0,
             icc_eoir1_el1: 0,
             icc_hppir0_el1: 0,
@@ -43,14 +100,57 @@ impl SystemRegisters {
             icc_igrpen0_el1: 0,
             icc_igrpen1_el1: 0,
             icc_pmr_el1: 0,
+            icc_rpr_el1: 0,
             icc_sgi0r_el1: 0,
             icc_sgi1r_el1: 0,
             icc_sre_el1: 0,
+            ich_ap0r0_el2: 0,
+            ich_ap0r1_el2: 0,
+            ich_ap0r2_el2: 0,
+            ich_ap0r3_el2: 0,
+            ich_ap1r0_el2: 0,
+            ich_ap1r1_el2: 0,
+            ich_ap1r2_el2: 0,
+            ich_ap1r3_el2: 0,
+            ich_eisr_el2: 0,
+            ich_elrsr_el2: 0,
+            ich_hcr_el2: 0,
+            ich_lr0_el2: 0,
+            ich_lr1_el2: 0,
+            ich_lr2_el2: 0,
+            ich_lr3_el2: 0,
+            ich_lr4_el2: 0,
+            ich_lr5_el2: 0,
+            ich_lr6_el2: 0,
+            ich_lr7_el2: 0,
+            ich_lr8_el2: 0,
+            ich_lr9_el2: 0,
+            ich_lr10_el2: 0,
+            ich_lr11_el2: 0,
+            ich_lr12_el2: 0,
+            ich_lr13_el2: 0,
+            ich_lr14_el2: 0,
+            ich_lr15_el2: 0,
+            ich_misr_el2: 0,
+            ich_vmcr_el2: 0,
+            ich_vtr_el2: 0,
             daif: 0,
         }
     }
 }
 
+/// Serialises unit tests which use the fake system registers, as tests may run in parallel.
+///
+/// All the fake system registers are reset to zero before this returns.
+#[cfg(test)]
+pub(crate) fn lock_for_test() -> MutexGuard<'static, ()> {
+    static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    let guard = TEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    *SYSREGS.lock().unwrap() = SystemRegisters::new();
+    guard
+}
+
 /// Generates a public function named `$function_name` to read the fake system register `$sysreg`.
 macro_rules! read_sysreg32 {
     ($sysreg:ident, $opc1:literal, $crm:ident, $crn:ident, $opc2: literal, $function_name:ident) => {
@@ -58,6 +158,10 @@ macro_rules! read_sysreg32 {
             crate::sysreg::fake::SYSREGS.lock().unwrap().$sysreg
         }
     };
+    // Only the aarch64 assembler needs the generic name.
+    (generic $($args:tt)*) => {
+        read_sysreg32!($($args)*);
+    };
 }
 
 /// Generates a public function named `$function_name` to write to the fake system register
@@ -80,6 +184,41 @@ macro_rules! write_sysreg64 {
     };
 }
 
+/// Generates a public function named `$function_name` to read the fake system register `$sysreg`.
+macro_rules! read_sysreg64_split {
+    (
+        $sysreg:ident,
+        $opc1:literal,
+        $crn:ident,
+        $crm_low:ident,
+        $crm_high:ident,
+        $opc2:literal,
+        $function_name:ident
+    ) => {
+        pub fn $function_name() -> u64 {
+            crate::sysreg::fake::SYSREGS.lock().unwrap().$sysreg
+        }
+    };
+}
+
+/// Generates a public function named `$function_name` to write to the fake system register
+/// `$sysreg`.
+macro_rules! write_sysreg64_split {
+    (
+        $sysreg:ident,
+        $opc1:literal,
+        $crn:ident,
+        $crm_low:ident,
+        $crm_high:ident,
+        $opc2:literal,
+        $function_name:ident
+    ) => {
+        pub fn $function_name(value: u64) {
+            crate::sysreg::fake::SYSREGS.lock().unwrap().$sysreg = value;
+        }
+    };
+}
+
 /// Disables debug, SError, IRQ and FIQ exceptions.
 pub fn irq_disable() {
     SYSREGS.lock().unwrap().daif = 0b11_1100_0000;